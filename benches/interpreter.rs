@@ -0,0 +1,57 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+use aneurysm::interpreter::*;
+
+/// The directory the benchmarked programs are read from (every `.b` file in it is benchmarked)
+const PROGRAMS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/benches/programs");
+
+/// Run a program to the end with no input, throwing its output away, and return how many instructions it executed
+fn run(code: &str) -> u64 {
+    let mut input = io::empty();
+    let mut output = io::sink();
+
+    let mut interpreter = Interpreter::new(code, InterpreterOptions::release()).unwrap();
+    interpreter.set_source(&mut input);
+    interpreter.set_sink(&mut output);
+    interpreter.run_to_end().unwrap();
+
+    interpreter.steps
+}
+
+fn programs() -> Vec<(String, String)> {
+    let mut paths = fs::read_dir(PROGRAMS_DIR)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "b"))
+        .collect::<Vec<PathBuf>>();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+            (name, fs::read_to_string(path).unwrap())
+        })
+        .collect()
+}
+
+fn run_to_end(c: &mut Criterion) {
+    let mut group = c.benchmark_group("run_to_end");
+    // some of the programs take a while to run
+    group.sample_size(10);
+
+    for (name, code) in programs() {
+        // report throughput in instructions (steps) per second
+        group.throughput(Throughput::Elements(run(&code)));
+        group.bench_function(name, |b| b.iter(|| run(&code)));
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, run_to_end);
+criterion_main!(benches);
@@ -0,0 +1,8 @@
+Counts down three nested cells from 255 to 0 for about 33 million instructions
+without printing anything in between
+
+-[
+    >-[
+        >-[-]
+    <-]
+<-]
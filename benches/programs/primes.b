@@ -0,0 +1,22 @@
+Prints the prime numbers below 100 one per line
+Checks every number by trial division with the divmod algorithm
+
+++>+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++[>>>[-]+<<[-]++<<[->>>+>>+<<<<<]>>>>>[-<<<<<+>>>>>]<<--[>>
+>>>>>[-]>[-]>[-]>[-]>[-]>[-]<<<<<<<<<<<<<<<[->>>>>>>>>>+<<<<<+<<<<<]>>>>>[-<<<<<
++>>>>>]<<<[->>>>>>>>>>+<<<<<<<+<<<]>>>[-<<<+>>>]>>>>>[->+>-[>+>>]>[+[-<+>]>+>>]<
+<<<<<]<<<<[-]+>>>>>>>[<<<<<<<[-]>>>>>>>[-]]<<<<<<<[<<[-]>>[-]]>>>>[-]>[-]>[-]>[-
+]>[-]>[-]<<<<<<<<<<<<<+>-]>[>>>>>>>>>>>>>>>>[-]>[-]>[-]>[-]>[-]>[-]>[-]>[-]>[-]>
+[-]>[-]>[-]<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<
++<<<<<]>>>>>[-<<<<<+>>>>>]>>>>>>>>>>>>>>>>>++++++++++<<[->+>-[>+>>]>[+[-<+>]>+>>
+]<<<<<<]>[-]>[-]>>[->+<]>>>++++++++++<<[->+>-[>+>>]>[+[-<+>]>+>>]<<<<<<]>[-]>[-]
+<<<<<<<<<<<<<<<<<<<<[-]>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<+<<<+>>>>>>>
+>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>>>>>>+<<<<<<<<<<<
+<<<<<<<<<<<<<]>>>[<+>>>>>>>>>>>>>>>>>>>>>>++++++++++++++++++++++++++++++++++++++
+++++++++++.<<<<<<<<<<<<<<<<<<<<<[-]]>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<+<
+<<+>>>>>>>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>>>>>+<<<<
+<<<<<<<<<<<<<<<<<<<]>>[->+<]>[>>>>>>>>>>>>>>>>>>>>++++++++++++++++++++++++++++++
+++++++++++++++++++.<<<<<<<<<<<<<<<<<<<<[-]]>>>>>>>>>>>>>>>++++++++++++++++++++++
+++++++++++++++++++++++++++.<<<<<<<<<<<<<<<<<<[-]++++++++++.[-]>>>>>>>>>>>>>>>[-]
+>[-]>[-]>[-]>[-]>[-]>[-]>[-]>[-]>[-]>[-]>[-]<<<<<<<<<<<<<<<<<<<<<<<<<<<[-]]<<<<+
+>-]
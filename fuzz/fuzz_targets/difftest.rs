@@ -0,0 +1,23 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use aneurysm::difftest::*;
+
+/// How many cells the programs get, kept small so that the pointer wraps around often
+const NUM_OF_CELLS: usize = 64;
+/// Stop programs after this many instructions, since most random programs never finish
+const STEP_LIMIT: u64 = 100_000;
+
+fuzz_target!(|data: &[u8]| {
+    // the first byte decides how much of the rest is the input of the program
+    let Some((&input_len, data)) = data.split_first() else {
+        return;
+    };
+    let (input, code) = data.split_at((input_len as usize).min(data.len()));
+
+    let program = balanced_program(code);
+    if let Some(divergence) = compare(&program, input, NUM_OF_CELLS, STEP_LIMIT).unwrap() {
+        panic!("{}\nprogram: {}\ninput: {:?}", divergence, program, input);
+    }
+});
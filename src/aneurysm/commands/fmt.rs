@@ -0,0 +1,52 @@
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use clap::Args;
+use displaydoc::Display;
+
+use aneurysm::formatter::format;
+use aneurysm::interpreter::*;
+
+#[derive(Args, Debug)]
+#[command(about = "Pretty-print a Brainf**k program")]
+pub struct FmtArgs {
+    /// Brainf**k file to format
+    #[arg(default_value = DEFAULT_FILENAME)]
+    filename: PathBuf,
+
+    /// Overwrite the file with the formatted program instead of printing it to stdout
+    #[arg(short, long)]
+    write: bool,
+
+    /// Don't print anything, just exit with an error if the file isn't formatted
+    #[arg(long, conflicts_with = "write")]
+    check: bool,
+}
+
+pub fn fmt(args: FmtArgs) -> Result<(), Box<dyn Error>> {
+    let code = fs::read_to_string(&args.filename)?;
+    let formatted = format(&code).map_err(FmtError::InterpreterError)?;
+
+    if args.check {
+        if code != formatted {
+            return Err(FmtError::NotFormatted(args.filename.display().to_string()).into());
+        }
+        log::info!("File \"{}\" is already formatted", args.filename.display());
+    } else if args.write {
+        fs::write(&args.filename, formatted)?;
+        log::info!("Formatted file \"{}\"", args.filename.display());
+    } else {
+        print!("{}", formatted);
+    }
+
+    Ok(())
+}
+
+#[derive(Display, thiserror::Error, Debug)]
+pub enum FmtError {
+    /// {0}
+    InterpreterError(InterpreterError),
+    /// File "{0}" isn't formatted
+    NotFormatted(String),
+}
@@ -0,0 +1,43 @@
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use clap::Args;
+
+use aneurysm::generator::generate_text;
+
+#[derive(Args, Debug)]
+#[command(about = "Generate a Brainf**k program that prints the given text")]
+pub struct GenTextArgs {
+    /// The text the program should print
+    text: String,
+
+    /// Append a newline to the text
+    #[arg(short, long)]
+    newline: bool,
+
+    /// Write the program to this file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+pub fn gen_text(args: GenTextArgs) -> Result<(), Box<dyn Error>> {
+    let mut text = args.text;
+    if args.newline {
+        text.push('\n');
+    }
+
+    let code = generate_text(&text);
+    log::info!(
+        "Generated a program of {} instructions to print {} bytes",
+        code.len(),
+        text.len()
+    );
+
+    match args.output {
+        Some(path) => fs::write(path, code)?,
+        None => println!("{}", code),
+    }
+
+    Ok(())
+}
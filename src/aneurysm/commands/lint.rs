@@ -0,0 +1,83 @@
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use clap::{Args, ValueEnum};
+use displaydoc::Display;
+use serde_json::json;
+
+use aneurysm::interpreter::*;
+use aneurysm::lint::lint as lint_code;
+
+#[derive(ValueEnum, Debug, Clone, Default)]
+pub enum LintFormat {
+    /// One lint per line, prefixed with its location
+    #[default]
+    Text,
+    /// A JSON array with all the lints
+    Json,
+}
+
+#[derive(Args, Debug)]
+#[command(about = "Look for likely bugs in Brainf**k programs")]
+pub struct LintArgs {
+    /// Brainf**k files to check
+    #[arg(default_value = DEFAULT_FILENAME)]
+    filenames: Vec<PathBuf>,
+
+    /// How to print the lints
+    #[arg(short, long, value_enum, default_value_t = LintFormat::default())]
+    format: LintFormat,
+}
+
+pub fn lint(args: LintArgs) -> Result<(), Box<dyn Error>> {
+    let mut json_lints = Vec::new();
+    let mut count = 0;
+
+    for filename in &args.filenames {
+        let code = fs::read_to_string(filename)?;
+        let lints = lint_code(&code).map_err(|err| LintError::InterpreterError {
+            file: filename.display().to_string(),
+            err,
+        })?;
+        count += lints.len();
+
+        for lint in lints {
+            match args.format {
+                LintFormat::Text => println!(
+                    "{}:{}: {}: {}",
+                    filename.display(),
+                    lint.position,
+                    lint.kind.name(),
+                    lint.kind
+                ),
+                LintFormat::Json => json_lints.push(json!({
+                    "file": filename,
+                    "index": lint.index,
+                    "line": lint.position.line,
+                    "column": lint.position.column,
+                    "lint": lint.kind.name(),
+                    "message": lint.kind.to_string(),
+                })),
+            }
+        }
+    }
+
+    if let LintFormat::Json = args.format {
+        println!("{}", serde_json::to_string_pretty(&json_lints)?);
+    }
+
+    if count > 0 {
+        return Err(LintError::LintsFound(count).into());
+    }
+
+    Ok(())
+}
+
+#[derive(Display, thiserror::Error, Debug)]
+pub enum LintError {
+    /// Couldn't lint file "{file}": {err}
+    InterpreterError { file: String, err: InterpreterError },
+    /// Found {0} lint(s)
+    LintsFound(usize),
+}
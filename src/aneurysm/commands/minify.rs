@@ -0,0 +1,51 @@
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use clap::Args;
+use displaydoc::Display;
+
+use aneurysm::interpreter::*;
+use aneurysm::minifier::minify as minify_code;
+
+#[derive(Args, Debug)]
+#[command(
+    about = "Strip comments and redundant instructions from a Brainf**k program",
+    long_about = "Strip comments and redundant instructions from a Brainf**k program\n\n\
+                  The minified program assumes that it starts on a zeroed tape, so loops at its very start are removed. \
+                  Don't minify programs meant to be run with --load-tape or --start-pointer"
+)]
+pub struct MinifyArgs {
+    /// Brainf**k file to minify
+    #[arg(default_value = DEFAULT_FILENAME)]
+    filename: PathBuf,
+
+    /// Write the minified program to this file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+pub fn minify(args: MinifyArgs) -> Result<(), Box<dyn Error>> {
+    let code = fs::read_to_string(&args.filename)?;
+    let minified = minify_code(&code).map_err(MinifyError::InterpreterError)?;
+
+    log::info!(
+        "Minified \"{}\" from {} to {} characters",
+        args.filename.display(),
+        code.chars().count(),
+        minified.len()
+    );
+
+    match args.output {
+        Some(path) => fs::write(path, minified)?,
+        None => println!("{}", minified),
+    }
+
+    Ok(())
+}
+
+#[derive(Display, thiserror::Error, Debug)]
+pub enum MinifyError {
+    /// {0}
+    InterpreterError(InterpreterError),
+}
@@ -0,0 +1,14 @@
+mod fmt;
+mod gen_text;
+mod lint;
+mod minify;
+mod tape_usage;
+mod test;
+mod translate;
+pub use fmt::*;
+pub use gen_text::*;
+pub use lint::*;
+pub use minify::*;
+pub use tape_usage::*;
+pub use test::*;
+pub use translate::*;
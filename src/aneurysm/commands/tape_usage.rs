@@ -0,0 +1,39 @@
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use clap::Args;
+use displaydoc::Display;
+
+use aneurysm::analysis::tape_usage as estimate_tape_usage;
+use aneurysm::interpreter::*;
+
+#[derive(Args, Debug)]
+#[command(about = "Estimate how many cells a Brainf**k program needs")]
+pub struct TapeUsageArgs {
+    /// Brainf**k file to analyze
+    #[arg(default_value = DEFAULT_FILENAME)]
+    filename: PathBuf,
+}
+
+pub fn tape_usage(args: TapeUsageArgs) -> Result<(), Box<dyn Error>> {
+    let code = fs::read_to_string(&args.filename)?;
+    let usage = estimate_tape_usage(&code)?;
+
+    println!("Cells touched: {} to {}", usage.min, usage.max);
+
+    match usage.suggested_memory() {
+        Some(memory) => println!("Suggested memory size: -m {}", memory),
+        None => {
+            return Err(TapeUsageCommandError::LeftOfOrigin(-usage.min as usize).into());
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Display, thiserror::Error, Debug)]
+pub enum TapeUsageCommandError {
+    /// The program moves up to {0} cell(s) left of cell 0 and relies on the data pointer wrapping around, no memory size is safe
+    LeftOfOrigin(usize),
+}
@@ -0,0 +1,321 @@
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use clap::Args;
+use displaydoc::Display;
+
+use aneurysm::interpreter::*;
+
+/// The default maximum number of instructions each test may execute
+const DEFAULT_STEP_LIMIT: u64 = 100_000_000;
+
+/// Header lines starting with this prefix define the input of a test
+const INPUT_HEADER: &str = "#input:";
+/// Header lines starting with this prefix define the expected output of a test
+const OUTPUT_HEADER: &str = "#output:";
+
+#[derive(Args, Debug)]
+#[command(about = "Run Brainf**k programs against their expected output")]
+pub struct TestArgs {
+    /// Files or directories (searched recursively) containing the tests
+    #[arg(default_value = ".")]
+    paths: Vec<PathBuf>,
+
+    /// The dialect the programs are written in (brainfuck, pbrain, brainfork, extended, boolfuck, ook, blub)
+    #[arg(short, long, default_value_t = Dialect::default())]
+    dialect: Dialect,
+
+    /// The maximum number of instructions each program may execute before it is considered stuck
+    #[arg(short, long, default_value_t = DEFAULT_STEP_LIMIT)]
+    step_limit: u64,
+
+    /// How many tests to run at the same time (defaults to the number of CPUs)
+    #[arg(short, long)]
+    jobs: Option<usize>,
+}
+
+/// A program along with its input and expected output
+struct Test {
+    path: PathBuf,
+    code: String,
+    input: Vec<u8>,
+    output: Vec<u8>,
+}
+
+/// Why a test failed
+#[derive(Display, Debug)]
+enum Failure {
+    /// {0}
+    InterpreterError(InterpreterError),
+    /// {0}
+    WrongOutput(String),
+}
+
+/// Replace escape sequences (`\n`, `\t`, `\0`, `\\` and `\xNN`) with the characters they stand for
+fn unescape(text: &str) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            bytes.extend(c.encode_utf8(&mut [0; 4]).as_bytes());
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => bytes.push(b'\n'),
+            Some('t') => bytes.push(b'\t'),
+            Some('0') => bytes.push(0),
+            Some('x') => {
+                let hex = chars.by_ref().take(2).collect::<String>();
+                match u8::from_str_radix(&hex, 16) {
+                    Ok(byte) => bytes.push(byte),
+                    Err(_) => bytes.extend(format!("\\x{}", hex).as_bytes()),
+                }
+            }
+            Some(other) => bytes.extend(other.encode_utf8(&mut [0; 4]).as_bytes()),
+            None => bytes.push(b'\\'),
+        }
+    }
+
+    bytes
+}
+
+/// Read the `#input:` and `#output:` lines at the start of `contents`, returning the code along with the input and expected output they define
+///
+/// Header lines are blanked in the returned code (since they may contain instructions) and are concatenated if repeated
+fn parse_header(contents: &str) -> (String, Option<Vec<u8>>, Option<Vec<u8>>) {
+    let mut input = None;
+    let mut output = None;
+    let mut code = Vec::new();
+    let mut in_header = true;
+
+    for line in contents.split('\n') {
+        let header = [(INPUT_HEADER, &mut input), (OUTPUT_HEADER, &mut output)]
+            .into_iter()
+            .find_map(|(prefix, value)| line.strip_prefix(prefix).map(|text| (text, value)));
+
+        match header {
+            Some((text, value)) if in_header => {
+                // files with CRLF line endings shouldn't end up with carriage returns in their input and output
+                let text = text.strip_suffix('\r').unwrap_or(text);
+                value
+                    .get_or_insert_with(Vec::new)
+                    .extend(unescape(text.strip_prefix(' ').unwrap_or(text)));
+                // keep the line numbers intact
+                code.push("");
+            }
+            _ => {
+                in_header = false;
+                code.push(line);
+            }
+        }
+    }
+
+    (code.join("\n"), input, output)
+}
+
+/// Load the test at `path`, returning `None` if it has no expected output
+///
+/// The input and output are read from the sibling `.in` and `.out` files, or from the header of the file (see [`parse_header`])
+fn load_test(path: &Path) -> io::Result<Option<Test>> {
+    let (code, input, output) = parse_header(&fs::read_to_string(path)?);
+
+    let read_sibling = |extension: &str| match fs::read(path.with_extension(extension)) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    };
+
+    let output = match read_sibling("out")?.or(output) {
+        Some(output) => output,
+        None => return Ok(None),
+    };
+    let input = read_sibling("in")?.or(input).unwrap_or_default();
+
+    Ok(Some(Test {
+        path: path.to_path_buf(),
+        code,
+        input,
+        output,
+    }))
+}
+
+/// Find all `.bf` files in `path` (or `path` itself, if it is a file)
+fn discover(path: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    if path.is_dir() {
+        for entry in fs::read_dir(path)? {
+            let entry_path = entry?.path();
+            if entry_path.is_dir() || entry_path.extension().is_some_and(|ext| ext == "bf") {
+                discover(&entry_path, files)?;
+            }
+        }
+    } else {
+        files.push(path.to_path_buf());
+    }
+
+    Ok(())
+}
+
+/// Describe the first difference between the expected and actual output
+fn describe_difference(expected: &[u8], actual: &[u8]) -> String {
+    let expected_lines = expected.split(|byte| *byte == b'\n').collect::<Vec<_>>();
+    let actual_lines = actual.split(|byte| *byte == b'\n').collect::<Vec<_>>();
+
+    let line = (0..expected_lines.len().max(actual_lines.len()))
+        .find(|index| expected_lines.get(*index) != actual_lines.get(*index))
+        .unwrap_or(0);
+    let show = |lines: &[&[u8]]| match lines.get(line) {
+        Some(text) => format!("{:?}", String::from_utf8_lossy(text)),
+        None => String::from("nothing"),
+    };
+
+    format!(
+        "output differs at line {}\n    expected: {}\n    actual:   {}",
+        line + 1,
+        show(&expected_lines),
+        show(&actual_lines)
+    )
+}
+
+fn run_test(test: &Test, args: &TestArgs) -> Result<(), Failure> {
+    let options = InterpreterOptions::release()
+        .with_dialect(args.dialect)
+        .with_step_limit(args.step_limit);
+
+    let mut input = io::Cursor::new(&test.input);
+    let mut output: Vec<u8> = Vec::new();
+
+    let mut interpreter =
+        Interpreter::new(&test.code, options).map_err(Failure::InterpreterError)?;
+    interpreter.set_source(&mut input);
+    interpreter.set_sink(&mut output);
+    interpreter
+        .run_to_end()
+        .map_err(Failure::InterpreterError)?;
+    drop(interpreter);
+
+    if output != test.output {
+        return Err(Failure::WrongOutput(describe_difference(
+            &test.output,
+            &output,
+        )));
+    }
+    Ok(())
+}
+
+pub fn test(args: TestArgs) -> Result<(), Box<dyn Error>> {
+    let mut files = Vec::new();
+    for path in &args.paths {
+        discover(path, &mut files)?;
+    }
+    files.sort();
+
+    let mut tests = Vec::new();
+    for file in files {
+        match load_test(&file)? {
+            Some(test) => tests.push(test),
+            None => log::info!("Skipping \"{}\", it has no expected output", file.display()),
+        }
+    }
+
+    let jobs = args
+        .jobs
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |jobs| jobs.get()))
+        .clamp(1, tests.len().max(1));
+
+    // each worker picks the next test that hasn't been run yet
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..tests.len()).map(|_| None).collect::<Vec<_>>());
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(test) = tests.get(index) else {
+                    break;
+                };
+
+                let result = run_test(test, &args);
+                results.lock().unwrap()[index] = Some(result);
+            });
+        }
+    });
+
+    let mut failed = 0;
+    for (test, result) in tests.iter().zip(results.into_inner().unwrap()) {
+        match result.unwrap() {
+            Ok(()) => println!("PASS {}", test.path.display()),
+            Err(failure) => {
+                failed += 1;
+                println!("FAIL {}: {}", test.path.display(), failure);
+            }
+        }
+    }
+
+    println!(
+        "\n{} passed, {} failed, {} total",
+        tests.len() - failed,
+        failed,
+        tests.len()
+    );
+
+    if failed > 0 {
+        return Err(TestError::TestsFailed(failed).into());
+    }
+    Ok(())
+}
+
+#[derive(Display, thiserror::Error, Debug)]
+pub enum TestError {
+    /// {0} test(s) failed
+    TestsFailed(usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes() {
+        assert_eq!(unescape(r"a\n\t\0\\b"), b"a\n\t\0\\b");
+        assert_eq!(unescape(r"\x41\x7e"), b"A~");
+        // invalid escapes are kept as they are
+        assert_eq!(unescape(r"\xzz\q\"), b"\\xzzq\\");
+        assert_eq!(unescape("é"), "é".as_bytes());
+    }
+
+    #[test]
+    fn header() {
+        let (code, input, output) = parse_header(
+            "#input: abc\n#output: Hello\\n\n#output: World\n,.\n#input: not a header",
+        );
+        assert_eq!(code, "\n\n\n,.\n#input: not a header");
+        assert_eq!(input.unwrap(), b"abc");
+        assert_eq!(output.unwrap(), b"Hello\nWorld");
+
+        let (code, input, output) = parse_header("#output: Hi\r\n#input:x\r\n+.\r\n");
+        assert_eq!(code, "\n\n+.\r\n");
+        assert_eq!(input.unwrap(), b"x");
+        assert_eq!(output.unwrap(), b"Hi");
+
+        let (_, input, output) = parse_header("+.");
+        assert!(input.is_none() && output.is_none());
+    }
+
+    #[test]
+    fn difference() {
+        assert_eq!(
+            describe_difference(b"one\ntwo\nthree", b"one\nfour"),
+            "output differs at line 2\n    expected: \"two\"\n    actual:   \"four\""
+        );
+        assert_eq!(
+            describe_difference(b"one\ntwo", b"one"),
+            "output differs at line 2\n    expected: \"two\"\n    actual:   nothing"
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use clap::Args;
+use displaydoc::Display;
+
+use aneurysm::interpreter::*;
+use aneurysm::tokens::*;
+
+#[derive(Args, Debug)]
+#[command(about = "Translate a program from one dialect to another (like Brainf**k to Ook!)")]
+pub struct TranslateArgs {
+    /// File to translate
+    #[arg(default_value = DEFAULT_FILENAME)]
+    filename: PathBuf,
+
+    /// The dialect the program is written in (brainfuck, pbrain, brainfork, extended, boolfuck, ook, blub)
+    #[arg(short, long, default_value_t = Dialect::default())]
+    from: Dialect,
+
+    /// The dialect to translate the program into (brainfuck, pbrain, brainfork, extended, boolfuck, ook, blub)
+    #[arg(short, long, default_value_t = Dialect::default())]
+    to: Dialect,
+
+    /// A file defining the tokens the program is written with (overrides --from)
+    #[arg(long, value_name = "FILE")]
+    from_tokens: Option<PathBuf>,
+
+    /// A file defining the tokens to translate the program into (overrides --to)
+    #[arg(long, value_name = "FILE")]
+    to_tokens: Option<PathBuf>,
+
+    /// Write the translated program to this file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+/// The user-defined tokens at `path` or, if there aren't any, the ones of `dialect`
+fn token_set(path: Option<PathBuf>, dialect: Dialect) -> Result<TokenSet, TranslateError> {
+    match path {
+        Some(path) => TokenSet::from_path(path).map_err(TranslateError::TokenSetError),
+        None => Ok(dialect
+            .token_set()
+            .unwrap_or_else(|| TokenSet::native(dialect))),
+    }
+}
+
+pub fn translate(args: TranslateArgs) -> Result<(), Box<dyn Error>> {
+    let from = token_set(args.from_tokens, args.from)?;
+    let to = token_set(args.to_tokens, args.to)?;
+
+    let code = fs::read_to_string(&args.filename)?;
+    let translated = to
+        .from_instructions(&from.to_instructions(&code))
+        .map_err(TranslateError::TokenSetError)?;
+
+    match args.output {
+        Some(path) => fs::write(path, translated)?,
+        None => println!("{}", translated),
+    }
+
+    Ok(())
+}
+
+#[derive(Display, thiserror::Error, Debug)]
+pub enum TranslateError {
+    /// {0}
+    TokenSetError(TokenSetError),
+}
@@ -1,38 +1,197 @@
-use clap::{arg, command, Parser};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
 use flexi_logger::Logger;
 use log::LevelFilter;
 
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::process::exit;
+use std::time::Instant;
 
 use aneurysm::*;
+use config::{Config, ConfigError};
+use coverage::Coverage;
 use interpreter::*;
+use preprocessor::*;
+use source::SourceMap;
+use tape::CellType;
+use tokens::{TokenSet, TokenSetError};
+use trace::*;
+
+mod commands;
+mod repl;
+
+/// The filename that makes the program be read from stdin
+const STDIN_PATH: &str = "-";
+/// The path errors in code passed with `-c --code` are reported against
+const INLINE_CODE_PATH: &str = "<code>";
+
+// clap exits with 2 on invalid arguments, so the codes of specific errors start at 3
+/// Any error that doesn't have a more specific exit code
+const EXIT_FAILURE: i32 = 1;
+/// The program (or its token set) couldn't be parsed
+const EXIT_SYNTAX_ERROR: i32 = 3;
+/// A file couldn't be read or written
+const EXIT_FILE_ERROR: i32 = 4;
+/// Reading the program's input or writing its output failed while it was running
+const EXIT_IO_ERROR: i32 = 5;
+/// The program was stopped by `--max-steps`
+const EXIT_STEP_LIMIT: i32 = 6;
+/// The program moved the data pointer out of the tape while `--strict-bounds` was set
+const EXIT_OUT_OF_BOUNDS: i32 = 7;
+
+use commands::*;
+use repl::repl;
 
 #[derive(Parser)]
 #[command(
     version,
-    about = "A Brainf**k interpreter written in Rust with minimal dependencies"
+    about = "A Brainf**k interpreter written in Rust with minimal dependencies",
+    args_conflicts_with_subcommands = true
 )]
 struct Args {
-    /// Brainf**k file to execute
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Brainf**k file to execute ("-" reads the program from stdin)
     #[arg(default_value = DEFAULT_FILENAME)]
     filename: String,
 
+    /// Execute this code instead of a file
+    #[arg(short, long, conflicts_with = "filename")]
+    code: Option<String>,
+
+    /// Read the program's input from this file instead of the console
+    #[arg(short, long, value_name = "FILE")]
+    input: Option<PathBuf>,
+
+    /// Use this text as the program's input instead of the console
+    #[arg(long, value_name = "TEXT", conflicts_with = "input")]
+    input_string: Option<String>,
+
+    /// Write the program's output to this file instead of stdout
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// What reading past the end of the input does to the current cell (unchanged, zero, max, error)
+    #[arg(long, value_name = "POLICY", default_value_t = EofPolicy::default())]
+    eof: EofPolicy,
+
     /// The memory size in bytes/cells to allocate for the program
     #[arg(short = 'm', long = "mem", default_value_t = DEFAULT_CELL_SIZE, value_name = "memory")]
     cell_size: usize,
 
     /// Enable verbose logging
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     verbose: bool,
 
     /// Whether or not to echo characters written to stdin
-    #[arg(short, long)]
+    #[arg(short, long, overrides_with = "no_echo")]
     echo: bool,
+
+    /// Don't echo characters written to stdin, even if the configuration file enables it
+    #[arg(long, overrides_with = "echo")]
+    no_echo: bool,
+
+    /// The dialect the program is written in (brainfuck, pbrain, brainfork, extended, boolfuck, ook, blub)
+    #[arg(short, long, default_value_t = Dialect::default())]
+    dialect: Dialect,
+
+    /// A file defining the tokens the program is written with (one instruction and its token per line, like "+ Ook. Ook.")
+    #[arg(short, long, value_name = "FILE")]
+    tokens: Option<PathBuf>,
+
+    /// Treat `#` as an instruction that prints the cells around the data pointer to stderr
+    #[arg(long)]
+    debug_dumps: bool,
+
+    /// Treat everything after the first `!` as the program's input
+    #[arg(long)]
+    input_separator: bool,
+
+    /// The type of the cells (byte, bit, wide). Defaults to the one of the dialect (bit for boolfuck, byte for everything else)
+    #[arg(long, value_name = "TYPE")]
+    cell_type: Option<CellType>,
+
+    /// Read non-ASCII characters from the console as UTF-8 and write output bytes as-is, so that UTF-8 output renders correctly
+    #[arg(long)]
+    utf8: bool,
+
+    /// Run the file through the preprocessor (includes, macros, repeat counts and cell aliases) before executing it
+    #[arg(short, long)]
+    preprocess: bool,
+
+    /// Write a record of every executed instruction to this file
+    #[arg(long, value_name = "FILE")]
+    trace: Option<PathBuf>,
+
+    /// The format of the trace (binary, jsonl)
+    #[arg(long, value_name = "FORMAT", default_value_t = TraceFormat::default(), requires = "trace")]
+    trace_format: TraceFormat,
+
+    /// Only trace the instructions in this range of indexes (like 10..20, 10.. or ..20)
+    #[arg(long, value_name = "RANGE", value_parser = parse_range, requires = "trace")]
+    trace_range: Option<Range<usize>>,
+
+    /// Write an LCOV coverage report of the executed instructions and loops to this file
+    #[arg(long, value_name = "FILE")]
+    coverage: Option<PathBuf>,
+
+    /// Load the initial contents of the tape from this binary file (one byte per byte cell, 8 bit cells per byte or 4 little-endian bytes per wide cell)
+    #[arg(long, value_name = "FILE")]
+    load_tape: Option<PathBuf>,
+
+    /// The cell the data pointer starts at
+    #[arg(long, value_name = "INDEX", default_value_t = 0)]
+    start_pointer: usize,
+
+    /// Write the final contents of the tape to this binary file (in the same format as --load-tape)
+    #[arg(long, value_name = "FILE")]
+    dump_tape: Option<PathBuf>,
+
+    /// Start an interactive prompt that runs each entered line on the same tape
+    #[arg(long, conflicts_with_all = ["filename", "code", "preprocess", "input", "input_string", "output", "trace", "coverage", "dump_tape", "exit_with_cell", "stats"])]
+    repl: bool,
+
+    /// Stop the program after executing this many instructions
+    #[arg(long, value_name = "STEPS")]
+    max_steps: Option<u64>,
+
+    /// Fail instead of wrapping the data pointer around when it is moved past either end of the tape
+    #[arg(long)]
+    strict_bounds: bool,
+
+    /// Use the value of the current cell as the exit code once the program finishes (values 1 and 3-7 can't be told apart from the error codes, and most systems only keep the lowest 8 bits)
+    #[arg(long)]
+    exit_with_cell: bool,
+
+    /// Print the number of executed instructions, the highest cell reached and the time taken to stderr
+    #[arg(long)]
+    stats: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    Fmt(FmtArgs),
+    GenText(GenTextArgs),
+    Lint(LintArgs),
+    Minify(MinifyArgs),
+    TapeUsage(TapeUsageArgs),
+    Test(TestArgs),
+    Translate(TranslateArgs),
 }
 
 fn main() {
+    // the configuration file provides the defaults of the command line parameters, so it has to be loaded first
+    let (config, config_error) = match Config::load_default() {
+        Ok(config) => (config, None),
+        Err(err) => (Config::default(), Some(err)),
+    };
+
     // Obtain command line parameters
-    let args = Args::parse();
+    let args = Args::from_arg_matches(&apply_config(Args::command(), &config).get_matches())
+        .unwrap_or_else(|err| err.exit());
 
     // Initialize logger
     let log_level = if args.verbose {
@@ -45,7 +204,7 @@ fn main() {
             LevelFilter::Info
         }
     } else {
-        LevelFilter::Warn
+        config.log_level.unwrap_or(LevelFilter::Warn)
     };
     Logger::try_with_env_or_str(log_level.to_string())
         .unwrap()
@@ -54,14 +213,352 @@ fn main() {
         .start()
         .unwrap();
 
-    let mut interpreter = Interpreter::new_from_path(
-        &args.filename,
-        InterpreterOptions::release().with_cell_size(args.cell_size),
-    )
-    .unwrap_or_else(|_| exit(1));
-    interpreter.set_stdout_echo(args.echo);
+    if let Some(err) = config_error {
+        log::error!("{}", err);
+        match err {
+            ConfigError::IOError(..) => exit(EXIT_FILE_ERROR),
+            ConfigError::ParseError(..) => exit(EXIT_SYNTAX_ERROR),
+        }
+    }
+
+    if let Some(command) = args.command {
+        let result = match command {
+            Command::Fmt(args) => fmt(args),
+            Command::GenText(args) => gen_text(args),
+            Command::Lint(args) => lint(args),
+            Command::Minify(args) => minify(args),
+            Command::TapeUsage(args) => tape_usage(args),
+            Command::Test(args) => test(args),
+            Command::Translate(args) => translate(args),
+        };
+
+        if let Err(err) = result {
+            log::error!("{}", err);
+            exit(1)
+        }
+        return;
+    }
+
+    let mut options = InterpreterOptions::release()
+        .with_cell_size(args.cell_size)
+        .with_dialect(args.dialect)
+        .with_debug_dumps(args.debug_dumps)
+        .with_input_separator(args.input_separator)
+        .with_utf8(args.utf8)
+        .with_eof(args.eof)
+        .with_strict_bounds(args.strict_bounds);
+    if let Some(max_steps) = args.max_steps {
+        options = options.with_step_limit(max_steps);
+    }
+    if let Some(cell_type) = args.cell_type {
+        options = options.with_cell_type(cell_type);
+    }
+    if let Some(path) = &args.tokens {
+        match TokenSet::from_path(path) {
+            Ok(tokens) => options = options.with_tokens(tokens),
+            Err(err) => {
+                log::error!("{}", err);
+                match err {
+                    TokenSetError::IOError(_) => exit(EXIT_FILE_ERROR),
+                    _ => exit(EXIT_SYNTAX_ERROR),
+                }
+            }
+        }
+    }
+
+    // the program is either inlined, read from stdin or (most commonly) read from a file
+    let code = match args.code {
+        Some(code) => Some((code, INLINE_CODE_PATH)),
+        None if args.repl => Some((String::new(), INLINE_CODE_PATH)),
+        None if args.filename == STDIN_PATH => {
+            let mut code = String::new();
+            if let Err(err) = io::stdin().read_to_string(&mut code) {
+                log::error!("Couldn't read the program from stdin: {}", err);
+                exit(EXIT_FILE_ERROR)
+            }
+            Some((code, STDIN_PATH))
+        }
+        None => None,
+    };
+
+    let mut input: Option<Box<dyn Read>> = match (args.input, args.input_string) {
+        (Some(path), _) => Some(Box::new(File::open(&path).unwrap_or_else(|err| {
+            log::error!("Couldn't open input file \"{}\": {}", path.display(), err);
+            exit(EXIT_FILE_ERROR)
+        }))),
+        (None, Some(text)) => Some(Box::new(io::Cursor::new(text.into_bytes()))),
+        (None, None) => None,
+    };
+
+    let mut output = args.output.map(|path| {
+        BufWriter::new(File::create(&path).unwrap_or_else(|err| {
+            log::error!(
+                "Couldn't create output file \"{}\": {}",
+                path.display(),
+                err
+            );
+            exit(EXIT_FILE_ERROR)
+        }))
+    });
+
+    let mut trace = args.trace.map(|path| {
+        let writer = File::create(&path)
+            .and_then(|file| TraceWriter::new(BufWriter::new(file), args.trace_format))
+            .unwrap_or_else(|err| {
+                log::error!("Couldn't create trace file \"{}\": {}", path.display(), err);
+                exit(EXIT_FILE_ERROR)
+            });
+        match args.trace_range.clone() {
+            Some(range) => writer.with_range(range),
+            None => writer,
+        }
+    });
+
+    // positions are only needed for traces and coverage reports, so the source is only mapped for those
+    let mapping = trace.is_some() || args.coverage.is_some();
+    let tokens = options.token_set();
+    let mut source_map = None;
+    let mut interpreter = match (code, args.preprocess) {
+        (Some((code, path)), true) => match preprocess(&code, path) {
+            Ok(preprocessed) => {
+                if mapping {
+                    source_map = Some(preprocessed.instruction_map(tokens.as_ref()));
+                }
+                Interpreter::new(preprocessed.code, options)
+            }
+            Err(err) => preprocess_failed(err),
+        },
+        (Some((code, path)), false) => {
+            if mapping {
+                source_map = Some(code_source_map(&code, Path::new(path), tokens.as_ref()));
+            }
+            Interpreter::new(code, options)
+        }
+        (None, true) => match preprocess_file(&args.filename) {
+            Ok(preprocessed) => {
+                if mapping {
+                    source_map = Some(preprocessed.instruction_map(tokens.as_ref()));
+                }
+                Interpreter::new(preprocessed.code, options)
+            }
+            Err(err) => preprocess_failed(err),
+        },
+        (None, false) => {
+            if mapping {
+                // if the file can't be read, the interpreter reports it right after
+                source_map = fs::read_to_string(&args.filename)
+                    .ok()
+                    .map(|code| code_source_map(&code, Path::new(&args.filename), tokens.as_ref()));
+            }
+            Interpreter::new_from_path(&args.filename, options)
+        }
+    }
+    .unwrap_or_else(|err| match err {
+        // the interpreter already logged it
+        InterpreterError::IOError(_) => exit(EXIT_FILE_ERROR),
+        err => {
+            log::error!("{}", err);
+            exit(exit_code(&err))
+        }
+    });
+    if let Some(path) = &args.load_tape {
+        let result = fs::read(path)
+            .map_err(|err| err.to_string())
+            .and_then(|image| {
+                interpreter
+                    .data
+                    .load_image(&image)
+                    .map_err(|err| err.to_string())
+            });
+        if let Err(err) = result {
+            log::error!("Couldn't load tape \"{}\": {}", path.display(), err);
+            exit(EXIT_FILE_ERROR)
+        }
+    }
+    if args.start_pointer >= interpreter.data.len() {
+        log::error!(
+            "The data pointer can't start at cell {}, since there are only {} cells",
+            args.start_pointer,
+            interpreter.data.len()
+        );
+        exit(EXIT_FAILURE)
+    }
+    interpreter.data_pointer = args.start_pointer;
+    interpreter.set_stdout_echo(!args.no_echo && (args.echo || config.echo.unwrap_or(false)));
+    if let Some(input) = &mut input {
+        interpreter.set_source(input);
+    }
+    if let Some(output) = &mut output {
+        interpreter.set_sink(output);
+    }
+
+    if args.repl {
+        if let Err(err) = repl(&mut interpreter, tokens.as_ref()) {
+            log::error!("{}", err);
+            exit(EXIT_IO_ERROR)
+        }
+        return;
+    }
 
     log::info!("Start executing program...");
-    interpreter.run_to_end();
-    log::info!("Reached end of code data. Terminating...")
+    let mut coverage = args.coverage.as_ref().map(|_| Coverage::new(&interpreter));
+
+    let started = Instant::now();
+    let mut trace_error = None;
+    let result = if trace.is_some() || coverage.is_some() {
+        run_traced(
+            &mut interpreter,
+            trace.as_mut(),
+            coverage.as_mut(),
+            source_map.as_ref(),
+        )
+        .unwrap_or_else(|err| {
+            // the program itself didn't fail, it was stopped
+            trace_error = Some(err);
+            Ok(())
+        })
+    } else {
+        interpreter.run_to_end()
+    };
+    if args.stats {
+        eprintln!(
+            "steps:       {}\nmax pointer: {}\nwall time:   {:?}",
+            interpreter.steps,
+            interpreter.max_data_pointer,
+            started.elapsed()
+        );
+    }
+    let instruction_pointer = interpreter.instruction_pointer;
+    let cell = interpreter.data.get(interpreter.data_pointer);
+    let tape = args
+        .dump_tape
+        .as_ref()
+        .map(|path| (path, interpreter.data.to_image()));
+    drop(interpreter);
+
+    // like the output, the tape is written even if the program failed
+    if let Some((path, tape)) = tape {
+        if let Err(err) = fs::write(path, tape) {
+            log::error!("Couldn't write tape \"{}\": {}", path.display(), err);
+            exit(EXIT_FILE_ERROR)
+        }
+    }
+
+    // the coverage of a failed run is still useful
+    if let (Some(path), Some(coverage)) = (&args.coverage, coverage) {
+        let lcov = coverage.to_lcov(&source_map.unwrap_or_default());
+        if let Err(err) = fs::write(path, lcov) {
+            log::error!(
+                "Couldn't write coverage report \"{}\": {}",
+                path.display(),
+                err
+            );
+            exit(EXIT_FILE_ERROR)
+        }
+    }
+
+    if let Some(trace) = trace {
+        if let Err(err) = trace_error.map_or_else(|| trace.finish(), Err) {
+            log::error!("Couldn't write to the trace file: {}", err);
+            exit(EXIT_FILE_ERROR)
+        }
+    }
+
+    // whatever was written before an error should still end up in the file
+    if let Some(output) = &mut output {
+        if let Err(err) = output.flush() {
+            log::error!("Couldn't write to the output file: {}", err);
+            exit(EXIT_FILE_ERROR)
+        }
+    }
+
+    if let Err(err) = result {
+        log::error!(
+            "An error occured at instruction {}: {}",
+            instruction_pointer,
+            err
+        );
+        exit(exit_code(&err))
+    }
+    log::info!("Reached end of code data. Terminating...");
+
+    if args.exit_with_cell {
+        // most systems only keep the lowest 8 bits
+        exit(cell as i32)
+    }
+}
+
+/// Use the values of the configuration file as the defaults of the matching arguments, so that they can still be overridden
+fn apply_config(mut command: clap::Command, config: &Config) -> clap::Command {
+    if let Some(memory) = config.memory {
+        command = command.mut_arg("cell_size", |arg| arg.default_value(memory.to_string()));
+    }
+    if let Some(cell_type) = config.cell_type {
+        command = command.mut_arg("cell_type", |arg| arg.default_value(cell_type.to_string()));
+    }
+    if let Some(eof) = config.eof {
+        command = command.mut_arg("eof", |arg| arg.default_value(eof.to_string()));
+    }
+
+    command
+}
+
+/// The exit code of a program that failed with `error`
+fn exit_code(error: &InterpreterError) -> i32 {
+    match error {
+        InterpreterError::UnmatchedLoop | InterpreterError::UnmatchedProcedure => EXIT_SYNTAX_ERROR,
+        InterpreterError::IOError(_) => EXIT_IO_ERROR,
+        InterpreterError::StepLimitExceeded(_) => EXIT_STEP_LIMIT,
+        InterpreterError::OutOfBounds(_) => EXIT_OUT_OF_BOUNDS,
+        InterpreterError::UndefinedProcedure(_) | InterpreterError::EndOfInput => EXIT_FAILURE,
+    }
+}
+
+/// Log an error of the preprocessor and exit with the matching code
+fn preprocess_failed(err: PreprocessError) -> ! {
+    log::error!("{}", err);
+    match err.kind {
+        PreprocessErrorKind::IOError(..) => exit(EXIT_FILE_ERROR),
+        _ => exit(EXIT_SYNTAX_ERROR),
+    }
+}
+
+/// Run the program to the end, writing a record of every executed instruction to `trace` and counting it in `coverage`
+///
+/// Stops early (returning the outer error) if the trace can't be written, which is kept apart from the errors of the program itself
+fn run_traced<W: Write>(
+    interpreter: &mut Interpreter,
+    mut trace: Option<&mut TraceWriter<W>>,
+    mut coverage: Option<&mut Coverage>,
+    source_map: Option<&SourceMap>,
+) -> io::Result<InterpreterResult<()>> {
+    loop {
+        let step = match interpreter.run_step_traced() {
+            Ok(Some(step)) => step,
+            Ok(None) => break,
+            Err(err) => return Ok(Err(err)),
+        };
+
+        if let Some(trace) = &mut trace {
+            let position = source_map
+                .and_then(|source_map| {
+                    source_map.locate(interpreter.origin(step.instruction_pointer))
+                })
+                .map(|(_, position)| position);
+            trace.write(&TraceRecord::new(&step, position))?;
+        }
+        if let Some(coverage) = &mut coverage {
+            coverage.record(&step);
+        }
+    }
+
+    Ok(Ok(()))
+}
+
+/// Map the instructions of `code` back to `path` (to their tokens, if the code is written with any)
+fn code_source_map(code: &str, path: &Path, tokens: Option<&TokenSet>) -> SourceMap {
+    match tokens {
+        Some(tokens) => tokens.source_map(code, path.to_path_buf()),
+        None => SourceMap::from_code(code, path.to_path_buf()),
+    }
 }
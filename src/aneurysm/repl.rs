@@ -0,0 +1,105 @@
+use std::io::{self, Write};
+
+use aneurysm::interpreter::*;
+use aneurysm::tokens::TokenSet;
+
+/// The prompt shown before each line
+const PROMPT: &str = "bf> ";
+/// The prompt shown while brackets are left open
+const CONTINUATION_PROMPT: &str = "... ";
+
+/// How many brackets (or pbrain parentheses) are left open at the end of `code`, or `None` if one is closed before being opened
+fn open_brackets(code: &str, dialect: Dialect) -> Option<usize> {
+    let mut loops: usize = 0;
+    let mut procedures: usize = 0;
+
+    for c in code.chars() {
+        match c {
+            '[' => loops += 1,
+            ']' => loops = loops.checked_sub(1)?,
+            '(' if dialect == Dialect::Pbrain => procedures += 1,
+            ')' if dialect == Dialect::Pbrain => procedures = procedures.checked_sub(1)?,
+            _ => (),
+        }
+    }
+
+    Some(loops + procedures)
+}
+
+/// Read lines of code from stdin, running each of them on the same tape and showing the cells around the data pointer afterwards.
+/// Lines that leave brackets open are joined with the following ones until they are closed
+pub fn repl(interpreter: &mut Interpreter, tokens: Option<&TokenSet>) -> io::Result<()> {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    let mut pending = String::new();
+
+    loop {
+        if pending.is_empty() {
+            print!("{}", PROMPT)
+        } else {
+            print!("{}", CONTINUATION_PROMPT)
+        }
+        stdout.flush()?;
+
+        let mut line = String::new();
+        if stdin.read_line(&mut line)? == 0 {
+            // Ctrl+D
+            println!();
+            return Ok(());
+        }
+        pending.push_str(&line);
+
+        let instructions = match tokens {
+            Some(tokens) => tokens.to_instructions(&pending),
+            None => pending.clone(),
+        };
+        let code = match open_brackets(&instructions, interpreter.dialect()) {
+            Some(0) => std::mem::take(&mut pending),
+            Some(_) => continue,
+            None => {
+                eprintln!("Found an unmatched closing bracket, the code was discarded");
+                pending.clear();
+                continue;
+            }
+        };
+
+        if let Err(err) = interpreter.append_code(&code) {
+            eprintln!("{}", err);
+            continue;
+        }
+
+        // the step limit applies to each line, instead of the whole session
+        interpreter.steps = 0;
+
+        // make sure the state isn't printed right after the program's output
+        let mut last_output = None;
+        let result = loop {
+            match interpreter.run_step_traced() {
+                Ok(Some(step)) if step.instruction == '.' => last_output = Some(step.before),
+                Ok(Some(_)) => (),
+                Ok(None) => break Ok(()),
+                Err(err) => break Err(err),
+            }
+        };
+        if last_output.is_some_and(|value| value != '\n' as u32) {
+            println!();
+        }
+
+        if let Err(err) = result {
+            // skip the rest of the code, so that the next line doesn't run into the same error
+            interpreter.instruction_pointer = interpreter.code.len();
+            interpreter.call_stack.clear();
+            interpreter.threads.clear();
+            eprintln!(
+                "An error occured, the rest of the code was skipped: {}",
+                err
+            );
+        }
+
+        println!(
+            "pointer {}, {}",
+            interpreter.data_pointer,
+            dump_cells(&interpreter.data, interpreter.data_pointer)
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use displaydoc::Display;
+
+use crate::interpreter::*;
+use crate::source::*;
+
+/// Returns how many cells the data pointer moves during one iteration of the loop starting at `start`,
+/// or `None` if that can't be determined (because one of its inner loops isn't balanced)
+pub fn loop_offset(code: &[char], loops: &Loops, start: usize) -> Option<isize> {
+    let end = *loops.get_by_left(&start).unwrap();
+    let mut offset = 0;
+    let mut index = start + 1;
+
+    while index < end {
+        match code[index] {
+            '>' => offset += 1,
+            '<' => offset -= 1,
+            '[' => {
+                if loop_offset(code, loops, index)? != 0 {
+                    return None;
+                }
+                index = *loops.get_by_left(&index).unwrap();
+            }
+            _ => (),
+        }
+        index += 1;
+    }
+
+    Some(offset)
+}
+
+/// The range of cells a program may touch, relative to the cell the data pointer starts at
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TapeUsage {
+    /// The leftmost cell index (negative if the program moves left of cell 0)
+    pub min: isize,
+    /// The rightmost cell index
+    pub max: isize,
+}
+
+impl TapeUsage {
+    /// The smallest memory size that doesn't make the data pointer wrap around,
+    /// or `None` if the program moves left of cell 0 (and thus depends on wrapping around)
+    pub fn suggested_memory(&self) -> Option<usize> {
+        if self.min < 0 {
+            None
+        } else {
+            Some(self.max as usize + 1)
+        }
+    }
+}
+
+/// Compute the range of cells a program may touch
+///
+/// This only works if every loop of the program is pointer-balanced (meaning it doesn't move the data pointer overall),
+/// otherwise [`TapeUsageError::UnbalancedLoop`] is returned
+pub fn tape_usage(code: &str) -> Result<TapeUsage, TapeUsageError> {
+    let code = code.chars().collect::<Vec<char>>();
+    let loops = Interpreter::get_loop(&code)?;
+
+    // every loop must leave the data pointer where it found it
+    if let Some(start) = loops
+        .left_values()
+        .filter(|&&start| loop_offset(&code, &loops, start) != Some(0))
+        .min()
+    {
+        return Err(TapeUsageError::UnbalancedLoop(positions(&code)[*start]));
+    }
+
+    // since all loops are balanced, each iteration touches the same cells, so we only need to go through the code once
+    let mut data_pointer: isize = 0;
+    let mut usage = TapeUsage { min: 0, max: 0 };
+
+    for c in code {
+        match c {
+            '>' => data_pointer += 1,
+            '<' => data_pointer -= 1,
+            _ => continue,
+        }
+
+        usage.min = usage.min.min(data_pointer);
+        usage.max = usage.max.max(data_pointer);
+    }
+
+    Ok(usage)
+}
+
+#[derive(Display, thiserror::Error, Debug)]
+pub enum TapeUsageError {
+    /// {0}
+    InterpreterError(#[from] InterpreterError),
+    /// The loop at {0} isn't pointer-balanced, so the program's tape usage can't be determined statically
+    UnbalancedLoop(Position),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn balanced_program() {
+        const PROGRAM: &str = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+<<<<<<-]>>.";
+
+        let usage = tape_usage(PROGRAM).unwrap();
+        assert_eq!(usage, TapeUsage { min: 0, max: 6 });
+        assert_eq!(usage.suggested_memory(), Some(7));
+
+        assert_eq!(tape_usage("><<").unwrap().suggested_memory(), None);
+    }
+
+    #[test]
+    fn unbalanced_program() {
+        assert!(matches!(
+            tape_usage("+[->+[<]]"),
+            Err(TapeUsageError::UnbalancedLoop(Position {
+                line: 1,
+                column: 2
+            }))
+        ))
+    }
+}
@@ -0,0 +1,150 @@
+use std::fmt::Display;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use directories::ProjectDirs;
+use displaydoc::Display;
+use log::LevelFilter;
+use serde::{Deserialize, Deserializer};
+
+use crate::interpreter::EofPolicy;
+use crate::tape::CellType;
+
+/// The name of the configuration file, inside the configuration directory (see [`default_path`])
+pub const CONFIG_FILENAME: &str = "config.toml";
+
+/// The directories both `aneurysm` and `lobotomy` store their files in
+pub fn project_dirs() -> Option<ProjectDirs> {
+    ProjectDirs::from("dev", "oakchris1955", "lobotomy")
+}
+
+/// Where the configuration file is found (for example `~/.config/lobotomy/config.toml` on Linux)
+pub fn default_path() -> Option<PathBuf> {
+    project_dirs().map(|dirs| dirs.config_dir().join(CONFIG_FILENAME))
+}
+
+/// Defaults for the command line options of both binaries. Anything left unset keeps its usual default
+#[derive(Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// The number of cells to allocate
+    pub memory: Option<usize>,
+    #[serde(deserialize_with = "from_str")]
+    pub cell_type: Option<CellType>,
+    #[serde(deserialize_with = "from_str")]
+    pub eof: Option<EofPolicy>,
+    /// Whether to echo characters read from the console
+    pub echo: Option<bool>,
+    /// The level of the messages to log (unless `-v --verbose` is set)
+    #[serde(deserialize_with = "from_str")]
+    pub log_level: Option<LevelFilter>,
+    /// Options that only apply to the debugger
+    pub lobotomy: LobotomyConfig,
+}
+
+#[derive(Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct LobotomyConfig {
+    /// The format of the prompt, where `{file}`, `{instruction}` and `{pointer}` are replaced by the name of the file,
+    /// the instruction pointer and the data pointer respectively
+    pub prompt: Option<String>,
+    /// The default width of `memdump`
+    pub memdump_width: Option<usize>,
+    /// Whether `memdump` prints uppercase hex by default
+    pub memdump_uppercase: Option<bool>,
+}
+
+/// Deserialize a string with the [`FromStr`] implementation of `T`, like the command line arguments are parsed
+fn from_str<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    String::deserialize(deserializer)?
+        .parse()
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+impl Config {
+    pub fn parse(text: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(text)
+    }
+
+    /// Load the configuration file at `path`, returning the default configuration if there isn't one
+    pub fn load<P>(path: P) -> Result<Self, ConfigError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text)
+                .map_err(|err| ConfigError::ParseError(path.display().to_string(), err)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(ConfigError::IOError(path.display().to_string(), err)),
+        }
+    }
+
+    /// Load the configuration file from its [default path](default_path)
+    pub fn load_default() -> Result<Self, ConfigError> {
+        match default_path() {
+            Some(path) => Self::load(path),
+            None => Ok(Self::default()),
+        }
+    }
+}
+
+#[derive(Display, thiserror::Error, Debug)]
+pub enum ConfigError {
+    /// Couldn't read configuration file "{0}": {1}
+    IOError(String, #[source] io::Error),
+    /// Invalid configuration file "{0}": {1}
+    ParseError(String, #[source] toml::de::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let config = Config::parse(
+            r#"
+            memory = 65536
+            cell-type = "wide"
+            eof = "unchanged"
+            log-level = "info"
+
+            [lobotomy]
+            memdump-width = 15
+            memdump-uppercase = true
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.memory, Some(65536));
+        assert_eq!(config.cell_type, Some(CellType::Wide));
+        assert_eq!(config.eof, Some(EofPolicy::Unchanged));
+        assert_eq!(config.echo, None);
+        assert_eq!(config.log_level, Some(LevelFilter::Info));
+        assert_eq!(config.lobotomy.memdump_width, Some(15));
+        assert_eq!(config.lobotomy.prompt, None);
+
+        // typos shouldn't be silently ignored
+        assert!(Config::parse("memroy = 100").is_err());
+        assert!(Config::parse("eof = \"sometimes\"").is_err());
+    }
+
+    #[test]
+    /// A missing configuration file is the same as an empty one
+    fn missing() {
+        assert_eq!(
+            Config::load("this/file/does/not/exist.toml").unwrap(),
+            Config::default()
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::interpreter::*;
+use crate::source::SourceMap;
+
+/// The count of a line, and how many times each of its loops was entered and skipped (`None` if the loop was never reached)
+type LineCoverage = (u64, Vec<Option<(u64, u64)>>);
+
+/// How many times each instruction of a program was executed, and how many times each loop was entered or skipped
+#[derive(Clone, Debug)]
+pub struct Coverage {
+    /// The index of each instruction in the code the interpreter was created with (see [`Interpreter::origin`])
+    origins: Vec<usize>,
+    code: Vec<char>,
+    /// Whether the character at each index is an instruction (comments are only kept in the debug profile)
+    instructions: Vec<bool>,
+    hits: Vec<u64>,
+    /// How many times the loop starting at each `[` was entered and skipped
+    branches: Vec<[u64; 2]>,
+}
+
+impl Coverage {
+    /// Start tracking the coverage of the program run by `interpreter`
+    pub fn new(interpreter: &Interpreter) -> Self {
+        let dialect = interpreter.dialect();
+
+        Self {
+            origins: (0..interpreter.code.len())
+                .map(|index| interpreter.origin(index))
+                .collect(),
+            code: interpreter.code.clone(),
+            instructions: interpreter
+                .code
+                .iter()
+                .map(|c| dialect.is_instruction(*c))
+                .collect(),
+            hits: vec![0; interpreter.code.len()],
+            branches: vec![[0; 2]; interpreter.code.len()],
+        }
+    }
+
+    /// Record an executed instruction (see [`Interpreter::run_step_traced`])
+    pub fn record(&mut self, step: &Step) {
+        let Some(hits) = self.hits.get_mut(step.instruction_pointer) else {
+            return;
+        };
+        *hits += 1;
+
+        // the loop is entered unless the current cell was 0
+        if step.instruction == '[' {
+            let branch = if step.before != 0 { 0 } else { 1 };
+            self.branches[step.instruction_pointer][branch] += 1;
+        }
+    }
+
+    /// How many times the instruction at `index` was executed
+    pub fn hits(&self, index: usize) -> u64 {
+        self.hits.get(index).copied().unwrap_or(0)
+    }
+
+    /// How many times the loop starting at `index` was entered and skipped, if there is a `[` there
+    pub fn branch(&self, index: usize) -> Option<(u64, u64)> {
+        match self.code.get(index) {
+            Some('[') => {
+                let [entered, skipped] = self.branches[index];
+                Some((entered, skipped))
+            }
+            _ => None,
+        }
+    }
+
+    /// Export the coverage as an [LCOV](https://github.com/linux-test-project/lcov) tracefile, mapping instructions back to their source with `source_map`
+    ///
+    /// The count of each line is the highest count of its instructions, and each `[` has two branches: entering the loop and skipping it.
+    /// Instructions that can't be mapped to a source file are left out
+    pub fn to_lcov(&self, source_map: &SourceMap) -> String {
+        let mut files: BTreeMap<&Path, BTreeMap<usize, LineCoverage>> = BTreeMap::new();
+
+        for (index, origin) in self.origins.iter().enumerate() {
+            if !self.instructions[index] && self.hits[index] == 0 {
+                continue;
+            }
+            let Some((file, position)) = source_map.locate(*origin) else {
+                continue;
+            };
+
+            let (count, branches) = files
+                .entry(file)
+                .or_default()
+                .entry(position.line)
+                .or_default();
+            *count = (*count).max(self.hits[index]);
+            if let Some(branch) = self.branch(index) {
+                branches.push((self.hits[index] > 0).then_some(branch));
+            }
+        }
+
+        let mut lcov = String::from("TN:\n");
+        for (file, lines) in files {
+            writeln!(lcov, "SF:{}", file.display()).unwrap();
+
+            let (mut found, mut hit) = (0, 0);
+            for (line, (_, branches)) in &lines {
+                for (block, branch) in branches.iter().enumerate() {
+                    let (entered, skipped) = match branch {
+                        Some((entered, skipped)) => (entered.to_string(), skipped.to_string()),
+                        None => (String::from("-"), String::from("-")),
+                    };
+                    writeln!(lcov, "BRDA:{},{},0,{}", line, block, entered).unwrap();
+                    writeln!(lcov, "BRDA:{},{},1,{}", line, block, skipped).unwrap();
+
+                    found += 2;
+                    hit += branch.map_or(0, |(entered, skipped)| {
+                        (entered > 0) as usize + (skipped > 0) as usize
+                    });
+                }
+            }
+            writeln!(lcov, "BRF:{}\nBRH:{}", found, hit).unwrap();
+
+            for (line, (count, _)) in &lines {
+                writeln!(lcov, "DA:{},{}", line, count).unwrap();
+            }
+            writeln!(
+                lcov,
+                "LF:{}\nLH:{}",
+                lines.len(),
+                lines.values().filter(|(count, _)| *count > 0).count()
+            )
+            .unwrap();
+            lcov.push_str("end_of_record\n");
+        }
+
+        lcov
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lcov() {
+        let code = "+[->+<]\n>[-]\n[+]";
+        let mut interpreter = Interpreter::new(code, InterpreterOptions::release()).unwrap();
+        let mut coverage = Coverage::new(&interpreter);
+        while let Some(step) = interpreter.run_step_traced().unwrap() {
+            coverage.record(&step);
+        }
+
+        // the first loop is entered once and left on the second check
+        assert_eq!(coverage.hits(1), 1);
+        assert_eq!(coverage.branch(1), Some((1, 0)));
+        assert_eq!(coverage.branch(0), None);
+
+        let lcov = coverage.to_lcov(&SourceMap::from_code(code, "test.bf".into()));
+        assert_eq!(
+            lcov,
+            "TN:\n\
+             SF:test.bf\n\
+             BRDA:1,0,0,1\n\
+             BRDA:1,0,1,0\n\
+             BRDA:2,0,0,1\n\
+             BRDA:2,0,1,0\n\
+             BRDA:3,0,0,0\n\
+             BRDA:3,0,1,1\n\
+             BRF:6\n\
+             BRH:3\n\
+             DA:1,1\n\
+             DA:2,1\n\
+             DA:3,1\n\
+             LF:3\n\
+             LH:3\n\
+             end_of_record\n"
+        );
+    }
+
+    #[test]
+    fn unreached() {
+        let code = "[[-]+]";
+        let mut interpreter = Interpreter::new(code, InterpreterOptions::release()).unwrap();
+        let mut coverage = Coverage::new(&interpreter);
+        while let Some(step) = interpreter.run_step_traced().unwrap() {
+            coverage.record(&step);
+        }
+
+        let lcov = coverage.to_lcov(&SourceMap::from_code(code, "test.bf".into()));
+        assert!(lcov.contains("BRDA:1,0,1,1\n"));
+        assert!(lcov.contains("BRDA:1,1,0,-\nBRDA:1,1,1,-\n"));
+        assert!(lcov.contains("BRF:4\nBRH:1\nDA:1,1\n"));
+    }
+}
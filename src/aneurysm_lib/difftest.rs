@@ -0,0 +1,267 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::io;
+use std::rc::Rc;
+
+use displaydoc::Display;
+
+use crate::interpreter::*;
+use crate::ir::*;
+
+/// How the engines disagreed
+#[derive(Display, Clone, PartialEq, Eq, Debug)]
+pub enum DivergenceKind {
+    /// the data pointer is {interpreter} in the interpreter but {ir} in the IR
+    DataPointer { interpreter: usize, ir: usize },
+    /// cell {index} is {interpreter} in the interpreter but {ir} in the IR
+    Cell {
+        index: usize,
+        interpreter: u32,
+        ir: u32,
+    },
+    /// output byte {index} is {interpreter:?} in the interpreter but {ir:?} in the IR
+    Output {
+        index: usize,
+        interpreter: Option<u8>,
+        ir: Option<u8>,
+    },
+    /// the interpreter {interpreter} but the IR {ir}
+    Outcome { interpreter: String, ir: String },
+}
+
+/// The first point at which two engines disagreed while running the same program
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Divergence {
+    /// The index of the instruction after which the engines disagreed (or `None` if they only disagreed once the program finished)
+    pub position: Option<usize>,
+    /// The instruction at `position`
+    pub instruction: Option<char>,
+    pub kind: DivergenceKind,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.position, self.instruction) {
+            (Some(position), Some(instruction)) => write!(
+                f,
+                "Engines diverged at instruction {} ('{}'): {}",
+                position, instruction, self.kind
+            ),
+            _ => write!(f, "Engines diverged after finishing: {}", self.kind),
+        }
+    }
+}
+
+/// An output buffer that can still be read while an engine writes to it
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl io::Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Describe the result of a step, in a way that can be compared between engines
+fn outcome(result: &InterpreterResult<Option<()>>) -> String {
+    match result {
+        Ok(Some(())) => String::from("kept running"),
+        Ok(None) => String::from("finished"),
+        Err(error) => format!("failed with \"{}\"", error),
+    }
+}
+
+/// Run the same Brainf**k program and input through the interpreter and the [IR](crate::ir), returning the first point at which they disagree
+///
+/// Both engines are run in lockstep: after each IR operation, the interpreter executes the instructions the operation was compiled from,
+/// and the data pointers, the current cells and the output are compared. The whole tape is compared once the program finishes.
+/// Programs that run for more than `step_limit` instructions are stopped (without a divergence, since neither engine did anything wrong up to that point).
+/// Returns an error if the program couldn't be compiled (like when its brackets are unmatched)
+pub fn compare(
+    code: &str,
+    input: &[u8],
+    num_of_cells: usize,
+    step_limit: u64,
+) -> InterpreterResult<Option<Divergence>> {
+    let mut interpreter_input = io::Cursor::new(input);
+    let mut ir_input = io::Cursor::new(input);
+    let mut interpreter_sink = SharedBuffer::default();
+    let mut ir_sink = SharedBuffer::default();
+    let interpreter_output = interpreter_sink.0.clone();
+    let ir_output = ir_sink.0.clone();
+
+    let mut interpreter = Interpreter::new(
+        code,
+        InterpreterOptions::release()
+            .with_cell_size(num_of_cells)
+            .with_step_limit(step_limit),
+    )?;
+    interpreter.set_source(&mut interpreter_input);
+    interpreter.set_sink(&mut interpreter_sink);
+
+    // compile the code the interpreter runs, so that positions refer to the same instructions
+    let mut machine = Machine::new(
+        Program::compile(&interpreter.code)?,
+        num_of_cells,
+        EofPolicy::default(),
+    );
+    machine.set_source(&mut ir_input);
+    machine.set_sink(&mut ir_sink);
+
+    let instructions = interpreter.code.clone();
+    let divergence = |position: usize, kind| Divergence {
+        position: Some(position),
+        instruction: instructions.get(position).copied(),
+        kind,
+    };
+
+    let mut compared_output = 0;
+    loop {
+        let position = machine.position();
+        let ir_result = machine.run_step();
+
+        // execute the instructions the operation was compiled from
+        let target = machine.position();
+        let mut interpreter_result = interpreter.run_step();
+        while matches!(interpreter_result, Ok(Some(())))
+            && interpreter.instruction_pointer != target
+        {
+            interpreter_result = interpreter.run_step();
+        }
+
+        if matches!(
+            interpreter_result,
+            Err(InterpreterError::StepLimitExceeded(_))
+        ) {
+            return Ok(None);
+        }
+        if outcome(&ir_result) != outcome(&interpreter_result) {
+            return Ok(Some(divergence(
+                position,
+                DivergenceKind::Outcome {
+                    interpreter: outcome(&interpreter_result),
+                    ir: outcome(&ir_result),
+                },
+            )));
+        }
+
+        // compare the output written so far
+        {
+            let interpreter_output = interpreter_output.borrow();
+            let ir_output = ir_output.borrow();
+            let len = interpreter_output.len().max(ir_output.len());
+            if let Some(index) = (compared_output..len)
+                .find(|index| interpreter_output.get(*index) != ir_output.get(*index))
+            {
+                return Ok(Some(divergence(
+                    position,
+                    DivergenceKind::Output {
+                        index,
+                        interpreter: interpreter_output.get(index).copied(),
+                        ir: ir_output.get(index).copied(),
+                    },
+                )));
+            }
+            compared_output = len;
+        }
+
+        if !matches!(ir_result, Ok(Some(()))) {
+            break;
+        }
+
+        if interpreter.data_pointer != machine.data_pointer {
+            return Ok(Some(divergence(
+                position,
+                DivergenceKind::DataPointer {
+                    interpreter: interpreter.data_pointer,
+                    ir: machine.data_pointer,
+                },
+            )));
+        }
+        let index = machine.data_pointer;
+        if interpreter.data.get(index) != machine.data[index] as u32 {
+            return Ok(Some(divergence(
+                position,
+                DivergenceKind::Cell {
+                    index,
+                    interpreter: interpreter.data.get(index),
+                    ir: machine.data[index] as u32,
+                },
+            )));
+        }
+    }
+
+    // operations only change the current cell, but better safe than sorry
+    Ok((0..num_of_cells)
+        .find(|index| interpreter.data.get(*index) != machine.data[*index] as u32)
+        .map(|index| Divergence {
+            position: None,
+            instruction: None,
+            kind: DivergenceKind::Cell {
+                index,
+                interpreter: interpreter.data.get(index),
+                ir: machine.data[index] as u32,
+            },
+        }))
+}
+
+/// Turn arbitrary bytes into a Brainf**k program with balanced brackets (used to generate random programs when fuzzing)
+///
+/// Each byte is mapped to one of the eight instructions. Closing brackets without a matching opening bracket are dropped,
+/// and brackets that are still open at the end are closed
+pub fn balanced_program(bytes: &[u8]) -> String {
+    let mut program = String::with_capacity(bytes.len());
+    let mut depth = 0;
+
+    for byte in bytes {
+        let instruction = crate::tokens::INSTRUCTIONS[*byte as usize % 8];
+        match instruction {
+            '[' => depth += 1,
+            ']' if depth == 0 => continue,
+            ']' => depth -= 1,
+            _ => (),
+        }
+        program.push(instruction);
+    }
+
+    program.extend(std::iter::repeat_n(']', depth));
+    program
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// The interpreter and the IR should agree on random programs
+    fn random_programs() {
+        // xorshift, so that failures can be reproduced
+        let mut state: u32 = 0x2545_f491;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        };
+
+        for _ in 0..500 {
+            let bytes = (0..64).map(|_| next()).collect::<Vec<_>>();
+            let input = (0..8).map(|_| next()).collect::<Vec<_>>();
+            let program = balanced_program(&bytes);
+
+            if let Some(divergence) = compare(&program, &input, 16, 10_000).unwrap() {
+                panic!("{}\n{}", program, divergence);
+            }
+        }
+    }
+
+    #[test]
+    fn balanced() {
+        assert_eq!(balanced_program(&[7, 6, 6, 2, 7]), "[[+]]");
+        assert!(compare("[", b"", 16, 100).is_err());
+    }
+}
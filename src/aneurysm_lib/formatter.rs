@@ -0,0 +1,214 @@
+use crate::interpreter::*;
+
+/// The string used to indent loop bodies
+pub const INDENT: &str = "    ";
+
+/// Loops with no nested loops and at most this many instructions are kept on a single line (like `[-]` or `[->+<]`)
+pub const MAX_INLINE_LOOP_LEN: usize = 8;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TokenKind {
+    /// A run of `+` and `-`
+    Arithmetic,
+    /// A run of `<` and `>`
+    Movement,
+    /// A run of the same I/O instruction (`.` or `,`)
+    IO(char),
+    /// Anything that isn't an instruction or whitespace
+    Comment,
+}
+
+impl TokenKind {
+    fn of(c: char) -> Self {
+        match c {
+            '+' | '-' => TokenKind::Arithmetic,
+            '<' | '>' => TokenKind::Movement,
+            '.' | ',' => TokenKind::IO(c),
+            _ => TokenKind::Comment,
+        }
+    }
+}
+
+/// Builds the formatted output line by line
+struct Formatter {
+    output: String,
+    line: Vec<String>,
+    last_kind: Option<TokenKind>,
+    depth: usize,
+    /// How many newlines were found in the source since the last token
+    newlines: usize,
+}
+
+impl Formatter {
+    fn new() -> Self {
+        Self {
+            output: String::new(),
+            line: Vec::new(),
+            last_kind: None,
+            depth: 0,
+            newlines: 0,
+        }
+    }
+
+    /// Preserve (at most one) blank line between two lines of code
+    fn start_token(&mut self) {
+        if self.line.is_empty() && self.newlines >= 2 && !self.output.is_empty() {
+            self.output.push('\n');
+        }
+        self.newlines = 0;
+    }
+
+    fn push_char(&mut self, c: char) {
+        let kind = TokenKind::of(c);
+        self.start_token();
+
+        match self.line.last_mut() {
+            Some(token) if self.last_kind == Some(kind) => token.push(c),
+            _ => self.line.push(c.to_string()),
+        }
+
+        self.last_kind = Some(kind);
+    }
+
+    /// Whitespace only breaks comment words apart, instruction runs continue over it
+    fn push_whitespace(&mut self) {
+        if self.last_kind == Some(TokenKind::Comment) {
+            self.last_kind = None;
+        }
+    }
+
+    fn push_token(&mut self, token: String) {
+        self.start_token();
+        self.line.push(token);
+        self.last_kind = None;
+    }
+
+    fn push_newline(&mut self) {
+        self.flush_line();
+        self.newlines += 1;
+    }
+
+    fn flush_line(&mut self) {
+        if self.line.is_empty() {
+            return;
+        }
+
+        self.output.push_str(&INDENT.repeat(self.depth));
+        self.output.push_str(&self.line.join(" "));
+        self.output.push('\n');
+
+        self.line.clear();
+        self.last_kind = None;
+    }
+
+    fn finish(mut self) -> String {
+        self.flush_line();
+        self.output
+    }
+}
+
+/// Returns `true` if the loop is short and flat enough to be printed on one line
+fn is_inline_loop(code: &[char]) -> bool {
+    let mut instructions = 0;
+
+    for c in code {
+        match c {
+            '+' | '-' | '<' | '>' | '.' | ',' => instructions += 1,
+            c if c.is_whitespace() && *c != '\n' => (),
+            // nested loops, comments or newlines
+            _ => return false,
+        }
+    }
+
+    instructions <= MAX_INLINE_LOOP_LEN
+}
+
+/// Pretty-print a Brainf**k program
+///
+/// Each loop body is placed on its own indentation level, consecutive runs of `+`/`-` and `<`/`>` are grouped together and comments are preserved where they were found.
+/// Returns [`InterpreterError::UnmatchedLoop`] if the program contains unmatched loop brackets
+pub fn format(code: &str) -> InterpreterResult<String> {
+    let code = code.chars().collect::<Vec<char>>();
+    let loops = Interpreter::get_loop(&code)?;
+
+    let mut formatter = Formatter::new();
+    let mut index = 0;
+
+    while index < code.len() {
+        let c = code[index];
+
+        match c {
+            '[' => {
+                let end = *loops.get_by_left(&index).unwrap();
+
+                if is_inline_loop(&code[index + 1..end]) {
+                    let body: String = code[index + 1..end]
+                        .iter()
+                        .filter(|c| !c.is_whitespace())
+                        .collect();
+                    formatter.push_token(format!("[{}]", body));
+                    index = end;
+                } else {
+                    formatter.flush_line();
+                    formatter.push_token(String::from("["));
+                    formatter.flush_line();
+                    formatter.depth += 1;
+                }
+            }
+            ']' => {
+                formatter.flush_line();
+                formatter.depth -= 1;
+                formatter.push_token(String::from("]"));
+                formatter.flush_line();
+            }
+            '\n' => formatter.push_newline(),
+            c if c.is_whitespace() => formatter.push_whitespace(),
+            c => formatter.push_char(c),
+        }
+
+        index += 1;
+    }
+
+    Ok(formatter.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Runs of the same kind of instruction should be grouped, loops should be indented
+    fn nested_loops() {
+        const PROGRAM: &str = "++++[>++[>+<-]<-]>>.";
+
+        assert_eq!(
+            format(PROGRAM).unwrap(),
+            "++++\n[\n    > ++ [>+<-] < -\n]\n>> .\n"
+        )
+    }
+
+    #[test]
+    /// Short loops stay on the same line and comments stay where they were
+    fn comments_and_inline_loops() {
+        const PROGRAM: &str = "clear the cell [-]\n\n\n\nand print it .";
+
+        assert_eq!(
+            format(PROGRAM).unwrap(),
+            "clear the cell [-]\n\nand print it .\n"
+        )
+    }
+
+    #[test]
+    /// Formatting twice shouldn't change anything
+    fn idempotent() {
+        const PROGRAM: &str = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.";
+
+        let formatted = format(PROGRAM).unwrap();
+        assert_eq!(format(&formatted).unwrap(), formatted)
+    }
+
+    #[test]
+    fn unmatched_loop() {
+        assert!(matches!(format("[[]"), Err(InterpreterError::UnmatchedLoop)))
+    }
+}
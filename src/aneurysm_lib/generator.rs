@@ -0,0 +1,89 @@
+/// Returns the shortest code that adds `delta` to the current cell (the cell to its right is used as a loop counter and must be zero)
+fn add_code(delta: i16) -> String {
+    let (increment, decrement) = if delta >= 0 { ('+', '-') } else { ('-', '+') };
+    let amount = delta.unsigned_abs() as usize;
+
+    // the most straightforward way of doing it
+    let mut best = increment.to_string().repeat(amount);
+
+    // try to multiply the loop counter with each loop's body, and then add (or subtract) whatever is left
+    for counter in 2..=amount {
+        for body in [amount / counter, amount / counter + 1] {
+            let remainder = amount as isize - (counter * body) as isize;
+            let correction = match remainder {
+                r if r >= 0 => increment.to_string().repeat(r as usize),
+                r => decrement.to_string().repeat(r.unsigned_abs()),
+            };
+
+            let code = format!(
+                ">{}[<{}>-]<{}",
+                "+".repeat(counter),
+                increment.to_string().repeat(body),
+                correction
+            );
+
+            if code.len() < best.len() {
+                best = code;
+            }
+        }
+    }
+
+    best
+}
+
+/// Generate a Brainf**k program that prints `text` (encoded as UTF-8)
+///
+/// The program uses two cells: the first one holds the byte that was printed last and is reused for the next one,
+/// while the second one is used as a counter for multiplication loops whenever those are shorter than plain `+`/`-` runs
+pub fn generate_text(text: &str) -> String {
+    let mut code = String::new();
+    let mut current: u8 = 0;
+
+    for byte in text.bytes() {
+        // cells wrap around, so take the shortest way there
+        let delta = byte.wrapping_sub(current) as i8 as i16;
+
+        code.push_str(&add_code(delta));
+        code.push('.');
+
+        current = byte;
+    }
+
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+    use crate::interpreter::*;
+
+    fn run(program: String) -> Vec<u8> {
+        let mut output: Vec<u8> = Vec::new();
+        let mut input = io::empty();
+        let mut interpreter = Interpreter::new(program, InterpreterOptions::release()).unwrap();
+        interpreter.set_source(&mut input);
+        interpreter.set_sink(&mut output);
+        interpreter.run_to_end().unwrap();
+
+        output
+    }
+
+    #[test]
+    /// The generated program should print exactly what we asked for
+    fn prints_text() {
+        for text in ["Hello World!\n", "", "zzz", "~ \t\x7f\x01", "Ωμέγα 🦀"] {
+            assert_eq!(run(generate_text(text)), text.as_bytes(), "{:?}", text)
+        }
+    }
+
+    #[test]
+    /// Multiplication loops should make large jumps shorter than plain runs
+    fn compact() {
+        let program = generate_text("A");
+
+        assert!(program.len() < 'A' as usize);
+        assert_eq!(run(program), b"A")
+    }
+}
@@ -1,7 +1,10 @@
 use std::{
-    fs,
+    collections::{HashMap, VecDeque},
+    fmt, fs,
     io::{self, Write},
+    mem,
     path::Path,
+    str::FromStr,
 };
 
 use bimap::BiMap;
@@ -14,29 +17,85 @@ use thousands::Separable;
 
 use num_modular::Reducer;
 
+use crate::tape::*;
+use crate::tokens::TokenSet;
+
 /// The default filename to use in case one isn't specified by the user
 pub const DEFAULT_FILENAME: &str = "main.bf";
 
 /// The default cell size to use in case one isn't specified by the user
 pub const DEFAULT_CELL_SIZE: usize = 30000;
 
-type Loops = BiMap<usize, usize>;
+/// How many cells on each side of the data pointer the default debug hook prints
+pub const DEBUG_DUMP_RADIUS: usize = 8;
+
+pub type Loops = BiMap<usize, usize>;
 
 pub struct Interpreter<'a, 'b> {
     pub instruction_pointer: usize,
     pub data_pointer: usize,
+    /// The highest cell the data pointer has been at since the interpreter was created (or reset)
+    pub max_data_pointer: usize,
+    /// How many instructions have been executed since the interpreter was created (or reset)
+    pub steps: u64,
+    step_limit: Option<u64>,
+    strict_bounds: bool,
     data_modulo: num_modular::Vanilla<usize>,
 
     pub code: Vec<char>,
+    /// The index of each instruction in the code the interpreter was created with, if comments were removed from it
+    origins: Option<Vec<usize>>,
+    /// The length of the code the interpreter was created with (including any appended code), before comments were removed
+    source_len: usize,
     pub loops: Loops,
-    pub data: Vec<u8>,
+    pub data: Tape,
+    /// The storage register (only used by [`Dialect::Extended`])
+    pub storage: u32,
+
+    /// The matching parentheses of each procedure (only used by [`Dialect::Pbrain`])
+    pub procedures: Loops,
+    /// Maps each procedure's identifier to the index of its opening parenthesis
+    pub procedure_table: HashMap<u32, usize>,
+    /// The indexes of the `:` instructions that called the procedures currently being executed
+    pub call_stack: Vec<usize>,
+
+    /// The identifier of the thread currently being executed (only used by [`Dialect::Brainfork`])
+    pub thread_id: usize,
+    /// The threads waiting for their turn, in the order they will be run
+    pub threads: VecDeque<Thread>,
+    next_thread_id: usize,
 
     profile: InterpreterProfile,
+    dialect: Dialect,
+    tokens: Option<TokenSet>,
+    debug_dumps: bool,
+    input_separator: bool,
 
     /// If this is unset, will write to stdout
     pub sink: Option<&'a mut dyn io::Write>,
     /// If this is unset, will read from stdin
-    pub source: Option<&'b mut dyn io::Read>,
+    pub source: Option<Box<dyn io::Read + 'b>>,
+    /// The input found after the `!` separator, if enabled (see [`InterpreterOptions::with_input_separator`])
+    embedded_input: Option<Vec<u8>>,
+    /// Called on every `#` instruction, if enabled (see [`InterpreterOptions::with_debug_dumps`])
+    debug_hook: DebugHook,
+
+    /// Bytes read but not yet consumed by `,`, which are read before anything else.
+    /// These are either the rest of a UTF-8 encoded character read from the console (see [`InterpreterOptions::with_utf8`]) or the input of undone steps
+    pending_input: VecDeque<u8>,
+    utf8: bool,
+    eof: EofPolicy,
+
+    /// The bits read but not yet consumed by `,` and how many of them are left (only used by [`Dialect::Boolfuck`])
+    input_bits: (u8, u8),
+    /// The bits written by `;` but not yet flushed and how many of them there are (only used by [`Dialect::Boolfuck`])
+    output_bits: (u8, u8),
+
+    /// The most recent steps, oldest first, if recording is enabled (see [`InterpreterOptions::with_recording`])
+    history: VecDeque<Undo>,
+    recording: Option<usize>,
+    /// The input bytes consumed by the current step
+    consumed_input: Vec<u8>,
 
     _console: console::Term,
     _stdout_echo: bool,
@@ -61,9 +120,301 @@ impl Default for InterpreterProfile {
     }
 }
 
+/// What `,` does once there is nothing left to read
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum EofPolicy {
+    /// Leave the current cell as-is
+    Unchanged,
+    /// Set the current cell to 0
+    #[default]
+    Zero,
+    /// Set the current cell to its largest value (which is -1 for byte cells)
+    Max,
+    /// Stop with [`InterpreterError::EndOfInput`]
+    Error,
+}
+
+impl EofPolicy {
+    /// All the supported EOF policies
+    pub const ALL: &'static [EofPolicy] = &[
+        EofPolicy::Unchanged,
+        EofPolicy::Zero,
+        EofPolicy::Max,
+        EofPolicy::Error,
+    ];
+
+    /// The name of the policy, as it is parsed by [`FromStr`]
+    pub fn name(&self) -> &'static str {
+        match self {
+            EofPolicy::Unchanged => "unchanged",
+            EofPolicy::Zero => "zero",
+            EofPolicy::Max => "max",
+            EofPolicy::Error => "error",
+        }
+    }
+}
+
+impl fmt::Display for EofPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for EofPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        EofPolicy::ALL
+            .iter()
+            .find(|policy| policy.name().eq_ignore_ascii_case(s))
+            .copied()
+            .ok_or_else(|| {
+                format!(
+                    "unknown EOF policy \"{}\" (possible values: {})",
+                    s,
+                    EofPolicy::ALL
+                        .iter()
+                        .map(|policy| policy.name())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })
+    }
+}
+
+/// The state of the interpreter passed to a [`DebugHook`]
+pub struct DebugInfo<'d> {
+    pub instruction_pointer: usize,
+    pub data_pointer: usize,
+    pub data: &'d Tape,
+}
+
+/// What a single executed instruction did (see [`Interpreter::run_step_traced`])
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Step {
+    /// How many instructions had been executed after this one, starting from 1
+    pub number: u64,
+    pub instruction_pointer: usize,
+    pub instruction: char,
+    /// The identifier of the thread that executed the instruction (only used by [`Dialect::Brainfork`])
+    pub thread_id: usize,
+    pub data_pointer: usize,
+    /// The value of the cell at `data_pointer` before the instruction was executed
+    pub before: u32,
+    /// The value of the cell at `data_pointer` after the instruction was executed
+    pub after: u32,
+}
+
+/// A function called whenever a `#` instruction is executed
+pub type DebugHook = Box<dyn FnMut(&DebugInfo)>;
+
+/// The default [`DebugHook`], which prints the cells around the data pointer to stderr (the current one is surrounded by brackets)
+pub fn default_debug_hook(info: &DebugInfo) {
+    eprintln!(
+        "# instruction {}, {}",
+        info.instruction_pointer,
+        dump_cells(info.data, info.data_pointer)
+    );
+}
+
+/// Format the cells around the data pointer in hex, like `cells 0..9: [69] 00 00 ...` (the current one is surrounded by brackets)
+///
+/// Every cell is padded to the digits of the largest value its type can hold
+pub fn dump_cells(data: &Tape, data_pointer: usize) -> String {
+    let start = data_pointer.saturating_sub(DEBUG_DUMP_RADIUS);
+    let end = (data_pointer + DEBUG_DUMP_RADIUS + 1).min(data.len());
+    let digits = data.cell_type().hex_digits();
+
+    let cells = (start..end)
+        .map(|index| {
+            if index == data_pointer {
+                format!("[{:0digits$x}]", data.get(index))
+            } else {
+                format!("{:0digits$x}", data.get(index))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    format!("cells {}..{}: {}", start, end, cells)
+}
+
+/// A thread that isn't currently being executed (see [`Dialect::Brainfork`])
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Thread {
+    pub id: usize,
+    pub instruction_pointer: usize,
+    pub data_pointer: usize,
+}
+
+/// Everything a step changed, so that it can be undone (see [`InterpreterOptions::with_recording`])
+struct Undo {
+    instruction_pointer: usize,
+    data_pointer: usize,
+    thread_id: usize,
+    next_thread_id: usize,
+    /// The previous values of the cells the step may have changed (the current one and, for `Y`, the one to its right)
+    cells: [(usize, u32); 2],
+    storage: u32,
+    input_bits: (u8, u8),
+    output_bits: (u8, u8),
+    /// The input bytes the step consumed, which are read again once it's undone
+    input: Vec<u8>,
+    /// The identifier of the procedure the step defined and its previous definition (only used by [`Dialect::Pbrain`])
+    procedure: Option<(u32, Option<usize>)>,
+    /// How the step changed the call stack (only used by [`Dialect::Pbrain`])
+    call_stack: Option<CallStackChange>,
+    /// How the step changed the threads waiting for their turn (only used by [`Dialect::Brainfork`]).
+    /// Forks don't need to be saved, since they are the only steps that change `next_thread_id`
+    threads: ThreadChanges,
+}
+
+enum CallStackChange {
+    /// A procedure was called
+    Pushed,
+    /// A procedure returned to this caller
+    Popped(usize),
+}
+
+/// What [`Interpreter::schedule`] did to the threads
+#[derive(Default)]
+struct ThreadChanges {
+    /// Whether the current thread was sent to the back of the line
+    rotated: bool,
+    /// The threads that reached EOF and were dropped, in order
+    finished: Vec<Thread>,
+}
+
+/// The instruction set the interpreter understands
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Dialect {
+    /// Plain old Brainf**k
+    #[default]
+    Brainfuck,
+    /// Brainf**k with procedures: `(` and `)` define a procedure identified by the current cell's value, while `:` calls the procedure identified by the current cell's value
+    Pbrain,
+    /// Brainf**k with threads: `Y` forks the current thread. The current cell is set to 0 for the parent, while the child continues one cell to the right, which is set to 1.
+    /// Threads share the same memory and are run one instruction at a time in a round-robin fashion
+    Brainfork,
+    /// [Extended Brainfuck Type I](https://esolangs.org/wiki/Extended_Brainfuck#Extended_Type_I): `@` ends the program,
+    /// `$` copies the current cell into the storage register and `!` copies the storage register into the current cell,
+    /// `}` and `{` shift the current cell one bit to the right and left, `~` inverts its bits,
+    /// while `^`, `&` and `|` XOR, AND and OR it with the storage register
+    Extended,
+    /// [Boolfuck](https://esolangs.org/wiki/Boolfuck): cells are single bits which `+` flips (there is no `-`),
+    /// `,` reads a single bit and `;` writes one (instead of `.`). Bits are read and written in little-endian order,
+    /// and any bits left over when the program ends are padded with zeros and written as a whole byte
+    Boolfuck,
+    /// Brainf**k written with [Ook!](https://esolangs.org/wiki/Ook!) tokens
+    Ook,
+    /// Brainf**k written with [Blub](https://esolangs.org/wiki/Blub) tokens
+    Blub,
+}
+
+impl Dialect {
+    /// All the supported dialects
+    pub const ALL: &'static [Dialect] = &[
+        Dialect::Brainfuck,
+        Dialect::Pbrain,
+        Dialect::Brainfork,
+        Dialect::Extended,
+        Dialect::Boolfuck,
+        Dialect::Ook,
+        Dialect::Blub,
+    ];
+
+    /// The name of the dialect, as it is parsed by [`FromStr`]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dialect::Brainfuck => "brainfuck",
+            Dialect::Pbrain => "pbrain",
+            Dialect::Brainfork => "brainfork",
+            Dialect::Extended => "extended",
+            Dialect::Boolfuck => "boolfuck",
+            Dialect::Ook => "ook",
+            Dialect::Blub => "blub",
+        }
+    }
+
+    /// The tokens the dialect is written with, if they aren't plain instructions
+    pub fn token_set(&self) -> Option<TokenSet> {
+        match self {
+            Dialect::Ook => Some(TokenSet::ook()),
+            Dialect::Blub => Some(TokenSet::blub()),
+            _ => None,
+        }
+    }
+
+    /// The instructions of this dialect (after its tokens have been translated)
+    pub fn instructions(&self) -> &'static [char] {
+        const BRAINFUCK: &[char] = &['>', '<', '+', '-', '.', ',', '[', ']'];
+
+        match self {
+            Dialect::Pbrain => &['>', '<', '+', '-', '.', ',', '[', ']', '(', ')', ':'],
+            Dialect::Brainfork => &['>', '<', '+', '-', '.', ',', '[', ']', 'Y'],
+            Dialect::Extended => &[
+                '>', '<', '+', '-', '.', ',', '[', ']', '@', '$', '!', '}', '{', '~', '^', '&', '|',
+            ],
+            Dialect::Boolfuck => &['>', '<', '+', ',', ';', '[', ']'],
+            _ => BRAINFUCK,
+        }
+    }
+
+    /// Whether `c` is an instruction in this dialect (after its tokens have been translated)
+    pub fn is_instruction(&self, c: char) -> bool {
+        self.instructions().contains(&c)
+    }
+
+    /// The type of the cells programs of this dialect work with
+    pub fn cell_type(&self) -> CellType {
+        match self {
+            Dialect::Boolfuck => CellType::Bit,
+            _ => CellType::Byte,
+        }
+    }
+}
+
+impl fmt::Display for Dialect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Dialect {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Dialect::ALL
+            .iter()
+            .find(|dialect| dialect.name().eq_ignore_ascii_case(s))
+            .copied()
+            .ok_or_else(|| {
+                format!(
+                    "unknown dialect \"{}\" (possible values: {})",
+                    s,
+                    Dialect::ALL
+                        .iter()
+                        .map(|dialect| dialect.name())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })
+    }
+}
+
 pub struct InterpreterOptions {
     num_of_cells: usize,
     profile: InterpreterProfile,
+    dialect: Dialect,
+    tokens: Option<TokenSet>,
+    debug_dumps: bool,
+    input_separator: bool,
+    cell_type: Option<CellType>,
+    utf8: bool,
+    eof: EofPolicy,
+    step_limit: Option<u64>,
+    strict_bounds: bool,
+    recording: Option<usize>,
 }
 
 impl InterpreterOptions {
@@ -85,6 +436,78 @@ impl InterpreterOptions {
         self.num_of_cells = cell_size;
         self
     }
+
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// Translate the code using a user-defined token set (takes precedence over the tokens of the dialect)
+    pub fn with_tokens(mut self, tokens: TokenSet) -> Self {
+        self.tokens = Some(tokens);
+        self
+    }
+
+    /// Treat `#` as an instruction that calls the debug hook (see [`Interpreter::set_debug_hook`])
+    pub fn with_debug_dumps(mut self, debug_dumps: bool) -> Self {
+        self.debug_dumps = debug_dumps;
+        self
+    }
+
+    /// Treat everything after the first `!` as the program's input instead of code.
+    /// Ignored by dialects written with tokens (since `!` may be part of a token) and by dialects where `!` is an instruction
+    pub fn with_input_separator(mut self, input_separator: bool) -> Self {
+        self.input_separator = input_separator;
+        self
+    }
+
+    /// Use a different type of cells than the one of the dialect
+    pub fn with_cell_type(mut self, cell_type: CellType) -> Self {
+        self.cell_type = Some(cell_type);
+        self
+    }
+
+    /// Feed non-ASCII characters read from the console as their UTF-8 bytes and write bytes to stdout as-is,
+    /// instead of rejecting them and writing each byte as a Latin-1 character respectively
+    pub fn with_utf8(mut self, utf8: bool) -> Self {
+        self.utf8 = utf8;
+        self
+    }
+
+    /// What `,` does once there is nothing left to read (Boolfuck always reads zeros instead)
+    pub fn with_eof(mut self, eof: EofPolicy) -> Self {
+        self.eof = eof;
+        self
+    }
+
+    /// Stop with [`InterpreterError::StepLimitExceeded`] instead of executing more than `step_limit` instructions
+    pub fn with_step_limit(mut self, step_limit: u64) -> Self {
+        self.step_limit = Some(step_limit);
+        self
+    }
+
+    /// Stop with [`InterpreterError::OutOfBounds`] instead of wrapping the data pointer around when it is moved past either end of the tape
+    pub fn with_strict_bounds(mut self, strict_bounds: bool) -> Self {
+        self.strict_bounds = strict_bounds;
+        self
+    }
+
+    /// Record the last `max_steps` steps, so that they can be undone with [`Interpreter::step_back`].
+    /// Each recorded step takes up some memory, so this is off by default
+    pub fn with_recording(mut self, max_steps: usize) -> Self {
+        self.recording = Some(max_steps);
+        self
+    }
+
+    /// The type of cells the interpreter will use
+    pub fn cell_type(&self) -> CellType {
+        self.cell_type.unwrap_or(self.dialect.cell_type())
+    }
+
+    /// The token set the code will be translated with before being run, if any
+    pub fn token_set(&self) -> Option<TokenSet> {
+        self.tokens.clone().or_else(|| self.dialect.token_set())
+    }
 }
 
 impl Default for InterpreterOptions {
@@ -92,6 +515,16 @@ impl Default for InterpreterOptions {
         Self {
             num_of_cells: DEFAULT_CELL_SIZE,
             profile: InterpreterProfile::default(),
+            dialect: Dialect::default(),
+            tokens: None,
+            debug_dumps: false,
+            input_separator: false,
+            cell_type: None,
+            utf8: false,
+            eof: EofPolicy::default(),
+            step_limit: None,
+            strict_bounds: false,
+            recording: None,
         }
     }
 }
@@ -101,12 +534,35 @@ impl<'a, 'b> Interpreter<'a, 'b> {
     where
         S: ToString,
     {
+        let mut code = code.to_string();
+        let mut embedded_input = None;
+
+        // translate the tokens of the dialect into plain instructions, so that brackets can be matched
+        if let Some(tokens) = options.token_set() {
+            code = tokens.to_instructions(&code);
+        } else if options.input_separator && !options.dialect.is_instruction('!') {
+            if let Some(index) = code.find('!') {
+                embedded_input = Some(code.as_bytes()[index + 1..].to_vec());
+                code.truncate(index);
+            }
+        }
+
         // turn the code String into a char vector
-        let mut code = code.to_string().chars().collect::<Vec<char>>();
+        let mut code = code.chars().collect::<Vec<char>>();
 
-        // Remove all non-instruction characters
+        // Remove all non-instruction characters, remembering where the rest came from
+        let source_len = code.len();
+        let mut origins = None;
         if options.profile == InterpreterProfile::Release {
-            Self::remove_comments(&mut code);
+            let is_kept =
+                |c: char| options.dialect.is_instruction(c) || (options.debug_dumps && c == '#');
+
+            origins = Some(
+                (0..code.len())
+                    .filter(|index| is_kept(code[*index]))
+                    .collect(),
+            );
+            code.retain(|c| is_kept(*c));
         }
 
         log::debug!("Allocating memory... ");
@@ -125,8 +581,8 @@ impl<'a, 'b> Interpreter<'a, 'b> {
             )
         }
 
-        let mut data: Vec<u8> = vec![0_u8; options.num_of_cells];
-        data.iter_mut().for_each(|cell| *cell = 0);
+        let mut data = Tape::new(options.cell_type(), options.num_of_cells);
+        data.clear();
         log::debug!(
             "Allocated {} bytes in total",
             // In the 22nd General Conference on Weights and Measures, it was declared that:
@@ -138,16 +594,49 @@ impl<'a, 'b> Interpreter<'a, 'b> {
         Ok(Self {
             instruction_pointer: 0,
             data_pointer: 0,
+            max_data_pointer: 0,
+            steps: 0,
+            step_limit: options.step_limit,
+            strict_bounds: options.strict_bounds,
             data_modulo: num_modular::Vanilla::new(&options.num_of_cells),
 
             loops: Self::get_loop(&code)?,
+            procedures: match options.dialect {
+                Dialect::Pbrain => Self::get_procedures(&code)?,
+                _ => Loops::new(),
+            },
+            procedure_table: HashMap::new(),
+            call_stack: Vec::new(),
+            thread_id: 0,
+            threads: VecDeque::new(),
+            next_thread_id: 1,
             code,
+            origins,
+            source_len,
             data,
+            storage: 0,
 
             profile: options.profile,
-
-            source: None,
+            dialect: options.dialect,
+            tokens: options.tokens,
+            debug_dumps: options.debug_dumps,
+            input_separator: options.input_separator,
+
+            source: embedded_input
+                .clone()
+                .map(|input| Box::new(io::Cursor::new(input)) as Box<dyn io::Read>),
             sink: None,
+            embedded_input,
+            debug_hook: Box::new(default_debug_hook),
+            pending_input: VecDeque::new(),
+            utf8: options.utf8,
+            eof: options.eof,
+            input_bits: (0, 0),
+            output_bits: (0, 0),
+
+            history: VecDeque::new(),
+            recording: options.recording,
+            consumed_input: Vec::new(),
 
             _console: console::Term::stdout(),
             _stdout_echo: false,
@@ -182,75 +671,611 @@ impl<'a, 'b> Interpreter<'a, 'b> {
         }
     }
 
-    /// If this returns `None`, EOF was reached
-    pub fn run_step(&mut self) -> Option<()> {
+    /// The cell outside of the tape that `character` would move the data pointer to (or start a new thread on), if bounds are strict
+    fn out_of_bounds(&self, character: char) -> Option<isize> {
+        if !self.strict_bounds {
+            return None;
+        }
+
+        match character {
+            '>' if self.data_pointer + 1 == self.data.len() => Some(self.data.len() as isize),
+            'Y' if self.dialect == Dialect::Brainfork
+                && self.data_pointer + 1 == self.data.len() =>
+            {
+                Some(self.data.len() as isize)
+            }
+            '<' if self.data_pointer == 0 => Some(-1),
+            _ => None,
+        }
+    }
+
+    /// If this returns `Ok(None)`, EOF was reached (by every thread)
+    pub fn run_step(&mut self) -> InterpreterResult<Option<()>> {
         // Check if EOF was reached
         if self.instruction_pointer >= self.code.len() {
-            return None;
+            self.flush_bits()?;
+            return Ok(None);
+        }
+
+        if self.step_limit.is_some_and(|limit| self.steps >= limit) {
+            return Err(InterpreterError::StepLimitExceeded(self.steps));
         }
 
         // Get the next character to process
         let character = self.code[self.instruction_pointer];
 
+        // like the step limit, this is checked before the instruction is counted as executed
+        if let Some(cell) = self.out_of_bounds(character) {
+            return Err(InterpreterError::OutOfBounds(cell));
+        }
+        self.steps += 1;
+
+        let undo = self.recording.map(|_| self.undo_for(character));
+        self.consumed_input.clear();
+
         // Loop through each character and process it accordingly
         match character {
             '>' => self.data_modulo.add_in_place(&mut self.data_pointer, &1),
             '<' => self.data_modulo.sub_in_place(&mut self.data_pointer, &1),
-            '+' => self.data[self.data_pointer] = self.data[self.data_pointer].overflowing_add(1).0,
-            '-' => self.data[self.data_pointer] = self.data[self.data_pointer].overflowing_sub(1).0,
-            '.' => match &mut self.sink {
-                Some(writable) => writable.write_all(&[self.data[self.data_pointer]]).unwrap(),
-                None => {
-                    print!("{}", self.data[self.data_pointer] as char);
-                    io::stdout().flush().unwrap()
-                }
+            '+' => self.data.increment(self.data_pointer),
+            // Boolfuck has no `-`, and writes with `;` instead of `.` (comments are kept in the debug profile)
+            '-' | '.' if self.dialect == Dialect::Boolfuck => (),
+            '-' => self.data.decrement(self.data_pointer),
+            '.' if self.data.cell_type() == CellType::Wide => {
+                self.write_char(self.data.get(self.data_pointer))?
+            }
+            '.' => self.write_byte(self.data.get(self.data_pointer) as u8)?,
+            ',' if self.data.cell_type() == CellType::Wide => match self.read_char()? {
+                Some(c) => self.data.set(self.data_pointer, c as u32),
+                None => self.end_of_input()?,
             },
-            ',' => match &mut self.source {
-                Some(readable) => {
-                    let mut buf = [0u8];
-                    readable.read_exact(&mut buf).unwrap();
-                    self.data[self.data_pointer] = buf[0];
-                }
-                None => {
-                    while let Ok(c) = self._console.read_char() {
-                        if c.is_ascii() {
-                            self.data[self.data_pointer] = c as u8;
-
-                            if self._stdout_echo && self.sink.is_none() {
-                                self._console.write_all(&[c as u8]).unwrap();
-                                self._console.flush().unwrap();
-                            }
-                            break;
-                        } else {
-                            log::warn!("Non-ASCII character {} read from console", c)
-                        }
-                    }
-                }
+            ',' if self.dialect == Dialect::Boolfuck => {
+                let bit = self.read_bit()?;
+                self.data.set(self.data_pointer, bit as u32)
+            }
+            ',' => match self.read_byte()? {
+                Some(byte) => self.data.set(self.data_pointer, byte as u32),
+                None => self.end_of_input()?,
             },
+            ';' if self.dialect == Dialect::Boolfuck => {
+                self.write_bit(self.data.get(self.data_pointer) == 1)?
+            }
             '[' => {
-                if self.data[self.data_pointer] == 0 {
+                if self.data.get(self.data_pointer) == 0 {
                     self.instruction_pointer =
                         *self.loops.get_by_left(&self.instruction_pointer).unwrap()
                 }
             }
             ']' => {
-                if self.data[self.data_pointer] != 0 {
+                if self.data.get(self.data_pointer) != 0 {
                     self.instruction_pointer =
                         *self.loops.get_by_right(&self.instruction_pointer).unwrap()
                 }
             }
+            '(' if self.dialect == Dialect::Pbrain => {
+                // define the procedure and skip its body
+                self.procedure_table
+                    .insert(self.data.get(self.data_pointer), self.instruction_pointer);
+                self.instruction_pointer = *self
+                    .procedures
+                    .get_by_left(&self.instruction_pointer)
+                    .unwrap()
+            }
+            ')' if self.dialect == Dialect::Pbrain => {
+                // return to the caller
+                if let Some(caller) = self.call_stack.pop() {
+                    self.instruction_pointer = caller
+                }
+            }
+            ':' if self.dialect == Dialect::Pbrain => {
+                let identifier = self.data.get(self.data_pointer);
+                match self.procedure_table.get(&identifier) {
+                    Some(&start) => {
+                        self.call_stack.push(self.instruction_pointer);
+                        self.instruction_pointer = start
+                    }
+                    None => return Err(InterpreterError::UndefinedProcedure(identifier)),
+                }
+            }
+            '#' if self.debug_dumps => (self.debug_hook)(&DebugInfo {
+                instruction_pointer: self.instruction_pointer,
+                data_pointer: self.data_pointer,
+                data: &self.data,
+            }),
+            '@' if self.dialect == Dialect::Extended => {
+                // jump straight to EOF (the instruction pointer is incremented below)
+                self.instruction_pointer = self.code.len() - 1
+            }
+            '$' if self.dialect == Dialect::Extended => {
+                self.storage = self.data.get(self.data_pointer)
+            }
+            '!' if self.dialect == Dialect::Extended => {
+                self.data.set(self.data_pointer, self.storage)
+            }
+            '}' if self.dialect == Dialect::Extended => self
+                .data
+                .set(self.data_pointer, self.data.get(self.data_pointer) >> 1),
+            '{' if self.dialect == Dialect::Extended => self
+                .data
+                .set(self.data_pointer, self.data.get(self.data_pointer) << 1),
+            '~' if self.dialect == Dialect::Extended => self
+                .data
+                .set(self.data_pointer, !self.data.get(self.data_pointer)),
+            '^' if self.dialect == Dialect::Extended => self.data.set(
+                self.data_pointer,
+                self.data.get(self.data_pointer) ^ self.storage,
+            ),
+            '&' if self.dialect == Dialect::Extended => self.data.set(
+                self.data_pointer,
+                self.data.get(self.data_pointer) & self.storage,
+            ),
+            '|' if self.dialect == Dialect::Extended => self.data.set(
+                self.data_pointer,
+                self.data.get(self.data_pointer) | self.storage,
+            ),
+            'Y' if self.dialect == Dialect::Brainfork => {
+                // the child runs on the next cell
+                let child_data_pointer = self.data_modulo.add(&self.data_pointer, &1);
+
+                self.data.set(self.data_pointer, 0);
+                self.data.set(child_data_pointer, 1);
+
+                // the child starts from the next instruction
+                self.threads.push_back(Thread {
+                    id: self.next_thread_id,
+                    instruction_pointer: self.instruction_pointer + 1,
+                    data_pointer: child_data_pointer,
+                });
+                self.next_thread_id += 1;
+            }
             _ => (),
         };
 
         // Increment the instruction pointer for the next cycle
         self.instruction_pointer += 1;
 
-        Some(())
+        let thread_changes = self.schedule();
+        self.max_data_pointer = self.max_data_pointer.max(self.data_pointer);
+
+        if let (Some(mut undo), Some(max_steps)) = (undo, self.recording) {
+            undo.input = mem::take(&mut self.consumed_input);
+            undo.threads = thread_changes;
+            if self.history.len() >= max_steps {
+                self.history.pop_front();
+            }
+            self.history.push_back(undo);
+        }
+
+        Ok(Some(()))
+    }
+
+    /// Same as [`run_step`](Self::run_step), but also returns what the executed instruction did
+    pub fn run_step_traced(&mut self) -> InterpreterResult<Option<Step>> {
+        let instruction_pointer = self.instruction_pointer;
+        let thread_id = self.thread_id;
+        let data_pointer = self.data_pointer;
+        let before = self.data.get(data_pointer);
+
+        Ok(self.run_step()?.map(|()| Step {
+            number: self.steps,
+            instruction_pointer,
+            instruction: self.code[instruction_pointer],
+            thread_id,
+            data_pointer,
+            before,
+            after: self.data.get(data_pointer),
+        }))
+    }
+
+    /// Append code to the program, so that it is executed once the current code finishes (like in a REPL).
+    /// Tokens are translated and comments are removed like in [`new`](Self::new), but the input separator is ignored.
+    /// The appended code has to be balanced on its own: if it isn't, an error is returned and nothing is appended
+    pub fn append_code(&mut self, code: &str) -> InterpreterResult<()> {
+        let code = match self.tokens.clone().or_else(|| self.dialect.token_set()) {
+            Some(tokens) => tokens.to_instructions(code),
+            None => code.to_string(),
+        };
+        let mut appended = code.chars().collect::<Vec<char>>();
+
+        let mut appended_origins = None;
+        if self.profile == InterpreterProfile::Release {
+            let is_kept =
+                |c: char| self.dialect.is_instruction(c) || (self.debug_dumps && c == '#');
+
+            appended_origins = Some(
+                (0..appended.len())
+                    .filter(|index| is_kept(appended[*index]))
+                    .map(|index| self.source_len + index)
+                    .collect::<Vec<usize>>(),
+            );
+            appended.retain(|c| is_kept(*c));
+        }
+
+        // match the brackets of the whole program before changing anything
+        let source_len = self.source_len + code.chars().count();
+        let mut code = self.code.clone();
+        code.extend(appended);
+        let loops = Self::get_loop(&code)?;
+        let procedures = match self.dialect {
+            Dialect::Pbrain => Self::get_procedures(&code)?,
+            _ => Loops::new(),
+        };
+
+        if let (Some(origins), Some(appended_origins)) = (&mut self.origins, appended_origins) {
+            origins.extend(appended_origins);
+        }
+        self.loops = loops;
+        self.procedures = procedures;
+        self.code = code;
+        self.source_len = source_len;
+
+        Ok(())
+    }
+
+    /// The index of the instruction at `index` in the code the interpreter was created with (after translating any tokens).
+    /// These only differ in release mode, where comments are removed
+    pub fn origin(&self, index: usize) -> usize {
+        match &self.origins {
+            Some(origins) => origins.get(index).copied().unwrap_or(index),
+            None => index,
+        }
+    }
+
+    /// Save what executing `character` may change
+    fn undo_for(&self, character: char) -> Undo {
+        let next_cell = self.data_modulo.add(&self.data_pointer, &1);
+
+        Undo {
+            instruction_pointer: self.instruction_pointer,
+            data_pointer: self.data_pointer,
+            thread_id: self.thread_id,
+            next_thread_id: self.next_thread_id,
+            cells: [
+                (self.data_pointer, self.data.get(self.data_pointer)),
+                (next_cell, self.data.get(next_cell)),
+            ],
+            storage: self.storage,
+            input_bits: self.input_bits,
+            output_bits: self.output_bits,
+            input: Vec::new(),
+            procedure: match (self.dialect, character) {
+                (Dialect::Pbrain, '(') => {
+                    let identifier = self.data.get(self.data_pointer);
+                    Some((identifier, self.procedure_table.get(&identifier).copied()))
+                }
+                _ => None,
+            },
+            call_stack: match (self.dialect, character) {
+                // calling an undefined procedure is an error, so the step isn't recorded at all
+                (Dialect::Pbrain, ':') => Some(CallStackChange::Pushed),
+                (Dialect::Pbrain, ')') => {
+                    self.call_stack.last().copied().map(CallStackChange::Popped)
+                }
+                _ => None,
+            },
+            threads: ThreadChanges::default(),
+        }
+    }
+
+    /// Undo the last recorded step (see [`InterpreterOptions::with_recording`]), so that it's executed again next.
+    /// The input the step consumed is read again, but its output can't be taken back.
+    /// Returns `false` if there are no recorded steps left
+    pub fn step_back(&mut self) -> bool {
+        let Some(undo) = self.history.pop_back() else {
+            return false;
+        };
+
+        // put the threads back in line, reversing what `schedule` did
+        for thread in undo.threads.finished.into_iter().rev() {
+            let next = self.swap_thread(thread);
+            self.threads.push_front(next);
+        }
+        if undo.threads.rotated {
+            let previous = self.threads.pop_back().unwrap();
+            let next = self.swap_thread(previous);
+            self.threads.push_front(next);
+        }
+        // the child of a fork is at the end of the line
+        if self.next_thread_id != undo.next_thread_id {
+            self.threads.pop_back();
+        }
+
+        self.instruction_pointer = undo.instruction_pointer;
+        self.data_pointer = undo.data_pointer;
+        self.thread_id = undo.thread_id;
+        self.next_thread_id = undo.next_thread_id;
+        // restore the current cell last, in case both are the same cell
+        for (index, value) in undo.cells.into_iter().rev() {
+            self.data.set(index, value);
+        }
+        self.storage = undo.storage;
+        self.input_bits = undo.input_bits;
+        self.output_bits = undo.output_bits;
+        for byte in undo.input.into_iter().rev() {
+            self.pending_input.push_front(byte);
+        }
+        match undo.procedure {
+            Some((identifier, Some(start))) => {
+                self.procedure_table.insert(identifier, start);
+            }
+            Some((identifier, None)) => {
+                self.procedure_table.remove(&identifier);
+            }
+            None => (),
+        }
+        match undo.call_stack {
+            Some(CallStackChange::Pushed) => {
+                self.call_stack.pop();
+            }
+            Some(CallStackChange::Popped(caller)) => self.call_stack.push(caller),
+            None => (),
+        }
+        self.steps -= 1;
+
+        true
+    }
+
+    /// Undo steps until the instruction at `index` is the next one to be executed (undoing at least one step).
+    /// Returns `false` if the recorded steps ran out before that
+    pub fn run_back_to(&mut self, index: usize) -> bool {
+        while self.step_back() {
+            if self.instruction_pointer == index {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Whether steps are being recorded (see [`InterpreterOptions::with_recording`])
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// How many steps can currently be undone
+    pub fn recorded_steps(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Write some bytes to the sink (or stdout)
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        match &mut self.sink {
+            Some(writable) => writable.write_all(bytes),
+            None => {
+                let mut stdout = io::stdout();
+                stdout.write_all(bytes)?;
+                stdout.flush()
+            }
+        }
+    }
+
+    /// Write a single byte to the sink (or stdout)
+    fn write_byte(&mut self, byte: u8) -> io::Result<()> {
+        if self.sink.is_none() && !self.utf8 {
+            print!("{}", byte as char);
+            return io::stdout().flush();
+        }
+        self.write_bytes(&[byte])
     }
 
-    /// Runs `run_step` until it returns `None`
-    pub fn run_to_end(&mut self) {
-        while self.run_step().is_some() {}
+    /// Write a Unicode scalar value as UTF-8 (invalid ones are replaced by U+FFFD)
+    fn write_char(&mut self, value: u32) -> io::Result<()> {
+        let c = char::from_u32(value).unwrap_or(char::REPLACEMENT_CHARACTER);
+        self.write_bytes(c.encode_utf8(&mut [0; 4]).as_bytes())
+    }
+
+    /// Read a single character from the console, echoing it if needed. Returns `None` if nothing could be read
+    fn read_console_char(&mut self) -> io::Result<Option<char>> {
+        match self._console.read_char() {
+            Ok(c) => {
+                if self._stdout_echo && self.sink.is_none() {
+                    self._console
+                        .write_all(c.encode_utf8(&mut [0; 4]).as_bytes())?;
+                    self._console.flush()?;
+                }
+                Ok(Some(c))
+            }
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Apply the EOF policy to the current cell
+    fn end_of_input(&mut self) -> InterpreterResult<()> {
+        match self.eof {
+            EofPolicy::Unchanged => (),
+            EofPolicy::Zero => self.data.set(self.data_pointer, 0),
+            EofPolicy::Max => self
+                .data
+                .set(self.data_pointer, self.data.cell_type().max_value()),
+            EofPolicy::Error => return Err(InterpreterError::EndOfInput),
+        }
+        Ok(())
+    }
+
+    /// Fill `buf` with bytes from `readable`, returning `false` if EOF was reached before that
+    fn read_exact_or_eof(readable: &mut dyn io::Read, buf: &mut [u8]) -> io::Result<bool> {
+        match readable.read_exact(buf) {
+            Ok(()) => Ok(true),
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Fill `buf` with the pending input, followed by bytes from the source, returning `false` if EOF was reached before that
+    fn read_input(&mut self, buf: &mut [u8]) -> io::Result<bool> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            match self.pending_input.pop_front() {
+                Some(byte) => buf[filled] = byte,
+                None => break,
+            }
+            filled += 1;
+        }
+
+        let complete = filled == buf.len()
+            || match &mut self.source {
+                Some(readable) => Self::read_exact_or_eof(readable, &mut buf[filled..])?,
+                None => false,
+            };
+        // bytes read from the source before it ran out are lost either way
+        self.consumed_input
+            .extend_from_slice(&buf[..if complete { buf.len() } else { filled }]);
+        Ok(complete)
+    }
+
+    /// Read a single byte from the pending input, then the source (or stdin). Returns `None` once EOF is reached
+    fn read_byte(&mut self) -> io::Result<Option<u8>> {
+        let mut buf = [0u8];
+        if self.source.is_some() || !self.pending_input.is_empty() {
+            return Ok(self.read_input(&mut buf)?.then_some(buf[0]));
+        }
+
+        while let Some(c) = self.read_console_char()? {
+            if c.is_ascii() {
+                self.consumed_input.push(c as u8);
+                return Ok(Some(c as u8));
+            } else if self.utf8 {
+                // the rest of the bytes are read by the following `,` instructions
+                self.pending_input
+                    .extend(c.encode_utf8(&mut [0; 4]).as_bytes());
+                return Ok(self.read_input(&mut buf)?.then_some(buf[0]));
+            } else {
+                log::warn!("Non-ASCII character {} read from console", c)
+            }
+        }
+        Ok(None)
+    }
+
+    /// Read a whole UTF-8 encoded character from the pending input, then the source (or stdin).
+    /// Invalid UTF-8 is read as U+FFFD. Returns `None` once EOF is reached
+    fn read_char(&mut self) -> io::Result<Option<char>> {
+        if self.source.is_none() && self.pending_input.is_empty() {
+            let c = self.read_console_char()?;
+            if let Some(c) = c {
+                self.consumed_input
+                    .extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+            }
+            return Ok(c);
+        }
+
+        let mut buf = [0u8; 4];
+        if !self.read_input(&mut buf[..1])? {
+            return Ok(None);
+        }
+
+        let len = match buf[0].leading_ones() {
+            0 => 1,
+            len @ 2..=4 => len as usize,
+            _ => return Ok(Some(char::REPLACEMENT_CHARACTER)),
+        };
+        if !self.read_input(&mut buf[1..len])? {
+            // the character was cut off
+            return Ok(Some(char::REPLACEMENT_CHARACTER));
+        }
+
+        Ok(Some(
+            std::str::from_utf8(&buf[..len])
+                .ok()
+                .and_then(|s| s.chars().next())
+                .unwrap_or(char::REPLACEMENT_CHARACTER),
+        ))
+    }
+
+    /// Read the next bit of the input, least significant first (0 once EOF is reached)
+    fn read_bit(&mut self) -> io::Result<bool> {
+        if self.input_bits.1 == 0 {
+            let byte = self.read_byte()?.unwrap_or(0);
+            self.input_bits = (byte, 8);
+        }
+
+        let (byte, left) = self.input_bits;
+        self.input_bits = (byte >> 1, left - 1);
+        Ok(byte & 1 == 1)
+    }
+
+    /// Queue a bit to be written, least significant first, writing a byte once 8 of them have been queued
+    fn write_bit(&mut self, bit: bool) -> io::Result<()> {
+        let (byte, count) = self.output_bits;
+        self.output_bits = (byte | (bit as u8) << count, count + 1);
+
+        if self.output_bits.1 == 8 {
+            self.flush_bits()?;
+        }
+        Ok(())
+    }
+
+    /// Write any queued bits, padding them with zeros
+    fn flush_bits(&mut self) -> io::Result<()> {
+        if self.output_bits.1 > 0 {
+            let byte = self.output_bits.0;
+            self.output_bits = (0, 0);
+            self.write_byte(byte)?;
+        }
+        Ok(())
+    }
+
+    /// Replace the current thread with `thread`, returning the previous one
+    fn swap_thread(&mut self, thread: Thread) -> Thread {
+        Thread {
+            id: mem::replace(&mut self.thread_id, thread.id),
+            instruction_pointer: mem::replace(
+                &mut self.instruction_pointer,
+                thread.instruction_pointer,
+            ),
+            data_pointer: mem::replace(&mut self.data_pointer, thread.data_pointer),
+        }
+    }
+
+    /// Give the next thread in line its turn
+    fn schedule(&mut self) -> ThreadChanges {
+        let mut changes = ThreadChanges::default();
+
+        if self.instruction_pointer < self.code.len() {
+            if let Some(next) = self.threads.pop_front() {
+                let current = self.swap_thread(next);
+                self.threads.push_back(current);
+                changes.rotated = true;
+            }
+        }
+
+        // threads that reached EOF are done, make way for the rest
+        while self.instruction_pointer >= self.code.len() {
+            match self.threads.pop_front() {
+                Some(next) => {
+                    let finished = self.swap_thread(next);
+                    changes.finished.push(finished);
+                }
+                None => break,
+            }
+        }
+
+        changes
+    }
+
+    /// Make the thread with the given identifier the current one, so that it's executed next.
+    /// Since this changes the order of the threads, the steps recorded so far can't be undone afterwards.
+    /// Returns `false` if no such thread exists
+    pub fn switch_thread(&mut self, id: usize) -> bool {
+        if id == self.thread_id {
+            return true;
+        }
+
+        match self.threads.iter().position(|thread| thread.id == id) {
+            Some(index) => {
+                let thread = self.threads.remove(index).unwrap();
+                // the previous thread takes its place in line
+                let current = self.swap_thread(thread);
+                self.threads.insert(index, current);
+                self.history.clear();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Runs `run_step` until it returns `Ok(None)` or an error
+    pub fn run_to_end(&mut self) -> InterpreterResult<()> {
+        while self.run_step()?.is_some() {}
+        Ok(())
     }
 
     /// Ready the interpreter for another program run
@@ -258,9 +1283,30 @@ impl<'a, 'b> Interpreter<'a, 'b> {
         // Reset instruction and data pointer
         self.instruction_pointer = 0;
         self.data_pointer = 0;
+        self.max_data_pointer = 0;
+        self.steps = 0;
 
         // Reset data vector
-        self.data.iter_mut().for_each(|cell| *cell = 0);
+        self.data.clear();
+        self.storage = 0;
+        self.input_bits = (0, 0);
+        self.output_bits = (0, 0);
+        self.pending_input.clear();
+        self.history.clear();
+
+        // Forget about any procedures
+        self.procedure_table.clear();
+        self.call_stack.clear();
+
+        // Only the main thread is left
+        self.thread_id = 0;
+        self.threads.clear();
+        self.next_thread_id = 1;
+
+        // Read the embedded input from the start
+        if let Some(input) = &self.embedded_input {
+            self.source = Some(Box::new(io::Cursor::new(input.clone())));
+        }
 
         log::debug!("Program state successfully reset");
     }
@@ -274,13 +1320,22 @@ impl<'a, 'b> Interpreter<'a, 'b> {
         self.sink = Some(sink)
     }
 
-    /// An easy way to set an alternative program character input
+    /// An easy way to set an alternative program character input (replaces any embedded input)
     #[allow(dead_code)]
     pub fn set_source<R>(&mut self, source: &'b mut R)
     where
         R: io::Read,
     {
-        self.source = Some(source)
+        self.source = Some(Box::new(source));
+        self.embedded_input = None
+    }
+
+    /// Replace the function called on `#` instructions (only if they are enabled, see [`InterpreterOptions::with_debug_dumps`])
+    pub fn set_debug_hook<F>(&mut self, hook: F)
+    where
+        F: FnMut(&DebugInfo) + 'static,
+    {
+        self.debug_hook = Box::new(hook)
     }
 
     // Whether to echo data written to stdin back to stdout IF AND ONLY IF sink isn't set
@@ -292,49 +1347,78 @@ impl<'a, 'b> Interpreter<'a, 'b> {
         InterpreterOptions {
             num_of_cells: self.data_modulo.modulus(),
             profile: self.profile.clone(),
+            dialect: self.dialect,
+            tokens: self.tokens.clone(),
+            debug_dumps: self.debug_dumps,
+            input_separator: self.input_separator,
+            cell_type: Some(self.data.cell_type()),
+            utf8: self.utf8,
+            eof: self.eof,
+            step_limit: self.step_limit,
+            strict_bounds: self.strict_bounds,
+            recording: self.recording,
         }
     }
 
-    /// Remove all non-instruction characters
-    fn remove_comments(code: &mut Vec<char>) {
-        code.retain(|c| match c {
-            '>' | '<' | '+' | '-' | '.' | ',' | '[' | ']' => true,
-            _ => false,
-        })
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
     }
 
-    /// A looping function to get all matching loop brackets (returns [`InterpreterError::UnmatchedLoop`] if a bracket is unmatched)
-    fn get_loop(code: &Vec<char>) -> Result<Loops, InterpreterError> {
-        let mut loops = BiMap::new();
+    /// Remove all characters that aren't instructions in the given dialect
+    pub fn remove_comments(code: &mut Vec<char>, dialect: Dialect) {
+        code.retain(|c| dialect.is_instruction(*c))
+    }
+
+    /// Match each `open` character with the corresponding `close` one. Returns `None` if one of them is unmatched
+    fn get_pairs(code: &[char], open: char, close: char) -> Option<Loops> {
+        let mut pairs = BiMap::new();
 
         let mut stack: Vec<usize> = Vec::new();
 
         for (index, char) in code.iter().enumerate() {
-            match char {
-                '[' => stack.push(index),
-                ']' => {
-                    loops.insert(stack.pop().ok_or(InterpreterError::UnmatchedLoop)?, index);
-                }
-                _ => (),
+            if *char == open {
+                stack.push(index)
+            } else if *char == close {
+                pairs.insert(stack.pop()?, index);
             }
         }
 
         if !stack.is_empty() {
-            return Err(InterpreterError::UnmatchedLoop);
+            return None;
         }
 
-        Ok(loops)
+        Some(pairs)
+    }
+
+    /// A looping function to get all matching loop brackets (returns [`InterpreterError::UnmatchedLoop`] if a bracket is unmatched)
+    pub fn get_loop(code: &[char]) -> Result<Loops, InterpreterError> {
+        Self::get_pairs(code, '[', ']').ok_or(InterpreterError::UnmatchedLoop)
+    }
+
+    /// Same as [`get_loop`](Self::get_loop), but for the parentheses of [`Dialect::Pbrain`] procedures (returns [`InterpreterError::UnmatchedProcedure`] if a parenthesis is unmatched)
+    pub fn get_procedures(code: &[char]) -> Result<Loops, InterpreterError> {
+        Self::get_pairs(code, '(', ')').ok_or(InterpreterError::UnmatchedProcedure)
     }
 }
 
 pub type InterpreterResult<T> = Result<T, InterpreterError>;
 
-#[derive(Display, Debug)]
+#[derive(Display, thiserror::Error, Debug)]
 pub enum InterpreterError {
     /// Found unmatched loop brackets
     UnmatchedLoop,
+    /// Found unmatched procedure parentheses
+    UnmatchedProcedure,
+    /// Tried to call procedure {0}, which hasn't been defined
+    UndefinedProcedure(u32),
+    /// Tried to read past the end of the input
+    EndOfInput,
+    /// Stopped after executing {0} instructions
+    StepLimitExceeded(u64),
+    /// Moved the data pointer out of the tape (to cell {0})
+    OutOfBounds(isize),
     /// {0}
-    IOError(io::Error),
+    IOError(#[from] io::Error),
 }
 
 #[cfg(test)]
@@ -394,7 +1478,7 @@ mod tests {
         let mut output: Vec<u8> = Vec::new();
         let mut interpreter = Interpreter::new(PROGRAM, InterpreterOptions::release()).unwrap();
         interpreter.set_sink(&mut output);
-        interpreter.run_to_end();
+        interpreter.run_to_end().unwrap();
 
         assert_eq!(
             // Brainf**k programs output ASCII characters, which are valid UTF-8
@@ -417,7 +1501,7 @@ mod tests {
         interpreter.set_source(&mut input);
         interpreter.set_sink(&mut output);
         interpreter.set_stdout_echo(true);
-        interpreter.run_to_end();
+        interpreter.run_to_end().unwrap();
 
         assert_eq!(
             // Brainf**k programs output ASCII characters, which are valid UTF-8
@@ -425,4 +1509,395 @@ mod tests {
             INPUT
         )
     }
+
+    #[test]
+    /// Procedures should only be available in the pbrain dialect
+    fn pbrain() {
+        // define procedure 0 (prints an 'A'), then call it twice
+        const PROGRAM: &str = "(++++++++[>++++++++<-]>+.[-]<)::";
+        let options = || InterpreterOptions::release().with_dialect(Dialect::Pbrain);
+
+        let mut output: Vec<u8> = Vec::new();
+        let mut interpreter = Interpreter::new(PROGRAM, options()).unwrap();
+        interpreter.set_sink(&mut output);
+        interpreter.run_to_end().unwrap();
+        assert_eq!(output, b"AA");
+
+        // in plain Brainf**k, the procedure's body is run exactly once
+        let mut output: Vec<u8> = Vec::new();
+        let mut interpreter = Interpreter::new(PROGRAM, InterpreterOptions::release()).unwrap();
+        interpreter.set_sink(&mut output);
+        interpreter.run_to_end().unwrap();
+        assert_eq!(output, b"A");
+
+        let mut interpreter = Interpreter::new("+:", options()).unwrap();
+        assert!(matches!(
+            interpreter.run_to_end(),
+            Err(InterpreterError::UndefinedProcedure(1))
+        ));
+        assert!(matches!(
+            Interpreter::new("(()", options()),
+            Err(InterpreterError::UnmatchedProcedure)
+        ));
+    }
+
+    #[test]
+    /// `#` and `!` should only be honoured when enabled
+    fn debug_dumps_and_input_separator() {
+        use std::{cell::RefCell, rc::Rc};
+
+        const PROGRAM: &str = ",+#.,+#.!ab";
+        let options = || {
+            InterpreterOptions::release()
+                .with_debug_dumps(true)
+                .with_input_separator(true)
+        };
+
+        let dumps = Rc::new(RefCell::new(Vec::new()));
+        let mut output: Vec<u8> = Vec::new();
+        let mut interpreter = Interpreter::new(PROGRAM, options()).unwrap();
+        interpreter.set_sink(&mut output);
+        let hook_dumps = dumps.clone();
+        interpreter.set_debug_hook(move |info| {
+            hook_dumps
+                .borrow_mut()
+                .push((info.instruction_pointer, info.data.get(info.data_pointer)))
+        });
+        interpreter.run_to_end().unwrap();
+
+        // the embedded input should be read again after a reset
+        interpreter.reset();
+        interpreter.run_to_end().unwrap();
+        assert_eq!(
+            *dumps.borrow(),
+            [
+                (2, 'b' as u32),
+                (6, 'c' as u32),
+                (2, 'b' as u32),
+                (6, 'c' as u32)
+            ]
+        );
+        assert_eq!(output, b"bcbc");
+
+        // both are comments by default
+        let interpreter = Interpreter::new(PROGRAM, InterpreterOptions::release()).unwrap();
+        assert_eq!(interpreter.code.iter().collect::<String>(), ",+.,+.");
+    }
+
+    #[test]
+    /// The storage register and bitwise operators of Extended Type I
+    fn extended() {
+        // 6 -> storage, 12 ({), 12 ^ 6 = 10, ~10 = 245, 245 & 6 = 4, 4 | 6 = 6, 3 (}), then end before the last +
+        const PROGRAM: &str = "++++++${^.~.&.|.}.!.@+";
+
+        let mut output: Vec<u8> = Vec::new();
+        let mut interpreter = Interpreter::new(
+            PROGRAM,
+            InterpreterOptions::release().with_dialect(Dialect::Extended),
+        )
+        .unwrap();
+        interpreter.set_sink(&mut output);
+        interpreter.run_to_end().unwrap();
+
+        assert_eq!(interpreter.storage, 6);
+        assert_eq!(interpreter.data.get(0), 6);
+        assert_eq!(output, [10, 245, 4, 6, 3, 6]);
+    }
+
+    #[test]
+    /// Infinite loops should be stopped by the step limit
+    fn step_limit() {
+        let options = || InterpreterOptions::release().with_step_limit(100);
+
+        let mut interpreter = Interpreter::new("+[]", options()).unwrap();
+        assert!(matches!(
+            interpreter.run_to_end(),
+            Err(InterpreterError::StepLimitExceeded(100))
+        ));
+
+        let mut interpreter = Interpreter::new("++[-]", options()).unwrap();
+        interpreter.run_to_end().unwrap();
+        assert_eq!(interpreter.steps, 7);
+    }
+
+    #[test]
+    /// Appended code should run on the same tape, right after the existing code
+    fn append_code() {
+        let mut output: Vec<u8> = Vec::new();
+        let mut interpreter = Interpreter::new("++++++++", InterpreterOptions::release()).unwrap();
+        interpreter.set_sink(&mut output);
+        interpreter.run_to_end().unwrap();
+
+        interpreter
+            .append_code("[>++++++++<-] eight times eight")
+            .unwrap();
+        interpreter.append_code(">+.").unwrap();
+        assert!(matches!(
+            interpreter.append_code("[["),
+            Err(InterpreterError::UnmatchedLoop)
+        ));
+        interpreter.run_to_end().unwrap();
+
+        assert_eq!(interpreter.code.len(), 24);
+        // the comment is skipped, but still counted
+        assert_eq!(interpreter.origin(21), 39);
+        drop(interpreter);
+        assert_eq!(output, b"A");
+    }
+
+    #[test]
+    /// The data pointer should only wrap around if bounds aren't strict
+    fn strict_bounds() {
+        let options = || InterpreterOptions::release().with_cell_size(4);
+
+        let mut interpreter = Interpreter::new(">><<<", options()).unwrap();
+        interpreter.run_to_end().unwrap();
+        assert_eq!(
+            (interpreter.data_pointer, interpreter.max_data_pointer),
+            (3, 3)
+        );
+
+        let mut interpreter =
+            Interpreter::new(">><<<", options().with_strict_bounds(true)).unwrap();
+        assert!(matches!(
+            interpreter.run_to_end(),
+            Err(InterpreterError::OutOfBounds(-1))
+        ));
+        assert_eq!(
+            (interpreter.data_pointer, interpreter.max_data_pointer),
+            (0, 2)
+        );
+        // the instruction that failed isn't counted
+        assert_eq!(interpreter.steps, 4);
+
+        let mut interpreter = Interpreter::new(">>>>", options().with_strict_bounds(true)).unwrap();
+        assert!(matches!(
+            interpreter.run_to_end(),
+            Err(InterpreterError::OutOfBounds(4))
+        ));
+    }
+
+    #[test]
+    /// Undoing steps should restore the state (and the input) they started from
+    fn step_back() {
+        const PROGRAM: &str = ",>,[-<+>]<.";
+
+        let mut input = io::Cursor::new("ab");
+        let mut output: Vec<u8> = Vec::new();
+        let mut interpreter =
+            Interpreter::new(PROGRAM, InterpreterOptions::release().with_recording(1000)).unwrap();
+        interpreter.set_source(&mut input);
+        interpreter.set_sink(&mut output);
+
+        interpreter.run_to_end().unwrap();
+        assert_eq!(interpreter.data.get(0), 195);
+
+        // go back to right after the first `,`
+        assert!(interpreter.run_back_to(1));
+        assert_eq!(interpreter.steps, 1);
+        assert_eq!((interpreter.data.get(0), interpreter.data.get(1)), (97, 0));
+        assert!(!interpreter.run_back_to(5));
+        assert_eq!(interpreter.instruction_pointer, 0);
+        assert!(!interpreter.step_back());
+
+        // the same input should be read again
+        interpreter.run_to_end().unwrap();
+        assert_eq!(interpreter.data.get(0), 195);
+        assert_eq!(output, [195, 195]);
+
+        // only the last steps are recorded
+        let mut interpreter =
+            Interpreter::new("+++++", InterpreterOptions::release().with_recording(2)).unwrap();
+        interpreter.run_to_end().unwrap();
+        assert_eq!(interpreter.recorded_steps(), 2);
+        while interpreter.step_back() {}
+        assert_eq!(interpreter.data.get(0), 3);
+    }
+
+    #[test]
+    /// Each EOF policy should do what it says
+    fn eof() {
+        let run = |eof: EofPolicy| {
+            let mut input = io::Cursor::new("a");
+            let mut interpreter =
+                Interpreter::new("+++>,<,", InterpreterOptions::release().with_eof(eof)).unwrap();
+            interpreter.set_source(&mut input);
+            interpreter.run_to_end().map(|_| interpreter.data.get(0))
+        };
+
+        assert_eq!(run(EofPolicy::Unchanged).unwrap(), 3);
+        assert_eq!(run(EofPolicy::Zero).unwrap(), 0);
+        assert_eq!(run(EofPolicy::Max).unwrap(), 255);
+        assert!(matches!(
+            run(EofPolicy::Error),
+            Err(InterpreterError::EndOfInput)
+        ));
+    }
+
+    #[test]
+    /// Wide cells should read and write whole characters
+    fn wide_cells() {
+        let mut output: Vec<u8> = Vec::new();
+        let mut input = io::Cursor::new("éa\u{10ffff}");
+        let mut interpreter = Interpreter::new(
+            ",+.,.,+.-.",
+            InterpreterOptions::release().with_cell_type(CellType::Wide),
+        )
+        .unwrap();
+        interpreter.set_source(&mut input);
+        interpreter.set_sink(&mut output);
+        interpreter.run_to_end().unwrap();
+
+        assert_eq!(interpreter.data.get(0), 0x10ffff);
+        // every cell is as wide as the largest one
+        let dump = dump_cells(&interpreter.data, 1);
+        assert!(dump.starts_with("cells 0..10: 10ffff [000000] 000000"));
+        assert_eq!(String::from_utf8(output).unwrap(), "êa\0\u{10ffff}");
+    }
+
+    #[test]
+    /// Bits should be read and written in little-endian order, with leftover bits flushed at EOF
+    fn boolfuck() {
+        // copy the 8 bits of the input, then write a lone 1 bit
+        const PROGRAM: &str = ",;,;,;,;,;,;,;,;+;";
+
+        let mut output: Vec<u8> = Vec::new();
+        let mut input = io::Cursor::new("A");
+        let mut interpreter = Interpreter::new(
+            PROGRAM,
+            InterpreterOptions::release().with_dialect(Dialect::Boolfuck),
+        )
+        .unwrap();
+        interpreter.set_source(&mut input);
+        interpreter.set_sink(&mut output);
+        interpreter.run_to_end().unwrap();
+        assert_eq!(output, [b'A', 1]);
+
+        // `-` and `.` aren't instructions, while `+` only flips the bit
+        for options in [InterpreterOptions::release(), InterpreterOptions::debug()] {
+            let mut output: Vec<u8> = Vec::new();
+            let mut interpreter = Interpreter::new(
+                "+++;-- a comment with . dots",
+                options.with_dialect(Dialect::Boolfuck),
+            )
+            .unwrap();
+            interpreter.set_sink(&mut output);
+            interpreter.run_to_end().unwrap();
+            assert_eq!(interpreter.data.get(0), 1);
+            drop(interpreter);
+            assert_eq!(output, [1]);
+        }
+    }
+
+    #[test]
+    /// Forked threads should be run in a round-robin fashion
+    fn brainfork() {
+        // the parent increments (and prints) cell 0, which was set to 0, the child cell 1, which was set to 1
+        const PROGRAM: &str = "+++Y+.";
+
+        let mut output: Vec<u8> = Vec::new();
+        let mut interpreter = Interpreter::new(
+            PROGRAM,
+            InterpreterOptions::release().with_dialect(Dialect::Brainfork),
+        )
+        .unwrap();
+        interpreter.set_sink(&mut output);
+
+        // run up to (and including) the fork
+        for _ in 0..4 {
+            interpreter.run_step().unwrap();
+        }
+        assert_eq!(interpreter.thread_id, 1);
+        assert_eq!(interpreter.data_pointer, 1);
+        assert_eq!(interpreter.threads.len(), 1);
+
+        assert!(interpreter.switch_thread(0));
+        assert!(!interpreter.switch_thread(2));
+
+        interpreter.run_to_end().unwrap();
+        assert!(interpreter.threads.is_empty());
+        assert_eq!(output, [1, 2]);
+    }
+
+    #[test]
+    /// Undoing a fork should get rid of the child thread
+    fn brainfork_step_back() {
+        let mut interpreter = Interpreter::new(
+            "+++Y+",
+            InterpreterOptions::release()
+                .with_dialect(Dialect::Brainfork)
+                .with_recording(100),
+        )
+        .unwrap();
+
+        interpreter.run_to_end().unwrap();
+        assert!(interpreter.run_back_to(3));
+        assert_eq!(interpreter.thread_id, 0);
+        assert!(interpreter.threads.is_empty());
+        assert_eq!((interpreter.data.get(0), interpreter.data.get(1)), (3, 0));
+    }
+
+    #[test]
+    /// Undoing each step should restore the threads and the call stack exactly as they were before it
+    fn step_back_snapshots() {
+        let snapshot = |interpreter: &Interpreter| {
+            (
+                interpreter.instruction_pointer,
+                interpreter.data_pointer,
+                interpreter.thread_id,
+                interpreter.threads.clone(),
+                interpreter.call_stack.clone(),
+                (0..8)
+                    .map(|i| interpreter.data.get(i))
+                    .collect::<Vec<u32>>(),
+            )
+        };
+
+        // the last fork's child starts at EOF, so both of them finish on the same step
+        for (program, dialect) in [
+            ("Y>+Y<+Y", Dialect::Brainfork),
+            // procedure 2 calls procedure 1
+            ("+(>+<)+(-:+):", Dialect::Pbrain),
+        ] {
+            let mut interpreter = Interpreter::new(
+                program,
+                InterpreterOptions::release()
+                    .with_dialect(dialect)
+                    .with_recording(1000),
+            )
+            .unwrap();
+
+            let mut snapshots = vec![snapshot(&interpreter)];
+            while interpreter.run_step().unwrap().is_some() {
+                snapshots.push(snapshot(&interpreter));
+            }
+            snapshots.pop();
+
+            while let Some(expected) = snapshots.pop() {
+                assert!(interpreter.step_back());
+                assert_eq!(snapshot(&interpreter), expected);
+            }
+            assert!(!interpreter.step_back());
+        }
+    }
+
+    #[test]
+    /// Ook! programs should be translated before brackets are matched
+    fn ook() {
+        // prints an 'A' (65 = 5 * 13)
+        const PROGRAM: &str = "Ook. Ook. Ook. Ook. Ook. Ook. Ook. Ook. Ook. Ook.
+            Ook! Ook? Ook. Ook? Ook. Ook. Ook. Ook. Ook. Ook. Ook. Ook. Ook. Ook. Ook. Ook.
+            Ook. Ook. Ook. Ook. Ook. Ook. Ook. Ook. Ook. Ook. Ook. Ook. Ook. Ook.
+            Ook? Ook. Ook! Ook! Ook? Ook! Ook. Ook? Ook! Ook.";
+
+        let mut output: Vec<u8> = Vec::new();
+        let mut interpreter = Interpreter::new(
+            PROGRAM,
+            InterpreterOptions::release().with_dialect(Dialect::Ook),
+        )
+        .unwrap();
+        interpreter.set_sink(&mut output);
+        interpreter.run_to_end().unwrap();
+        assert_eq!(output, b"A");
+    }
 }
@@ -0,0 +1,290 @@
+use std::io::{self, Read, Write};
+
+use crate::interpreter::*;
+
+/// A single operation of the intermediate representation
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Op {
+    /// Add a value to the current cell (a run of `+` and `-`)
+    Add(u8),
+    /// Move the data pointer by some offset (a run of `>` and `<`)
+    Move(isize),
+    Output,
+    Input,
+    /// Set the current cell to 0 (`[-]` or `[+]`)
+    Clear,
+    /// Jump right after the operation at the given index if the current cell is 0 (`[`)
+    JumpIfZero(usize),
+    /// Jump right after the operation at the given index unless the current cell is 0 (`]`)
+    JumpIfNonZero(usize),
+}
+
+/// An operation, along with the index of the first instruction it was compiled from
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Instruction {
+    pub op: Op,
+    pub position: usize,
+}
+
+/// A Brainf**k program compiled into operations that can be executed faster than its characters
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Program {
+    pub instructions: Vec<Instruction>,
+    /// The number of characters the program was compiled from
+    pub code_len: usize,
+}
+
+impl Program {
+    /// Compile Brainf**k code (anything that isn't one of the eight instructions is ignored)
+    pub fn compile(code: &[char]) -> InterpreterResult<Self> {
+        let mut instructions: Vec<Instruction> = Vec::new();
+        let mut open_loops = Vec::new();
+        let mut position = 0;
+
+        while position < code.len() {
+            let op = match code[position] {
+                '+' | '-' => {
+                    let (value, len) = Self::run(code, position, |c| match c {
+                        '+' => Some(1),
+                        '-' => Some(-1),
+                        _ => None,
+                    });
+                    instructions.push(Instruction {
+                        op: Op::Add(value.rem_euclid(256) as u8),
+                        position,
+                    });
+                    position += len;
+                    continue;
+                }
+                '>' | '<' => {
+                    let (offset, len) = Self::run(code, position, |c| match c {
+                        '>' => Some(1),
+                        '<' => Some(-1),
+                        _ => None,
+                    });
+                    instructions.push(Instruction {
+                        op: Op::Move(offset),
+                        position,
+                    });
+                    position += len;
+                    continue;
+                }
+                '[' if matches!(code.get(position + 1..position + 3), Some(['-' | '+', ']'])) => {
+                    instructions.push(Instruction {
+                        op: Op::Clear,
+                        position,
+                    });
+                    position += 3;
+                    continue;
+                }
+                '.' => Op::Output,
+                ',' => Op::Input,
+                '[' => {
+                    open_loops.push(instructions.len());
+                    // patched once the matching bracket is found
+                    Op::JumpIfZero(0)
+                }
+                ']' => {
+                    let start = open_loops.pop().ok_or(InterpreterError::UnmatchedLoop)?;
+                    instructions[start].op = Op::JumpIfZero(instructions.len());
+                    Op::JumpIfNonZero(start)
+                }
+                _ => {
+                    position += 1;
+                    continue;
+                }
+            };
+
+            instructions.push(Instruction { op, position });
+            position += 1;
+        }
+
+        if !open_loops.is_empty() {
+            return Err(InterpreterError::UnmatchedLoop);
+        }
+
+        Ok(Self {
+            instructions,
+            code_len: code.len(),
+        })
+    }
+
+    /// Sum the values of the instructions starting at `start` for as long as `value` returns one, returning the sum and the length of the run
+    fn run(code: &[char], start: usize, value: impl Fn(char) -> Option<isize>) -> (isize, usize) {
+        let mut sum = 0;
+        let mut len = 0;
+
+        // comments can't break a run, since they are ignored anyway
+        while let Some(&c) = code.get(start + len) {
+            match value(c) {
+                Some(delta) => sum += delta,
+                None if !crate::tokens::INSTRUCTIONS.contains(&c) => (),
+                None => break,
+            }
+            len += 1;
+        }
+
+        (sum, len)
+    }
+}
+
+/// Executes a compiled [`Program`] on byte cells
+pub struct Machine<'a, 'b> {
+    pub program: Program,
+    /// The index of the next operation to execute
+    pub instruction_pointer: usize,
+    pub data_pointer: usize,
+    pub data: Vec<u8>,
+    /// How many operations have been executed
+    pub steps: u64,
+    eof: EofPolicy,
+
+    /// If this is unset, will write to stdout
+    pub sink: Option<&'a mut dyn io::Write>,
+    /// If this is unset, will read from stdin
+    pub source: Option<Box<dyn io::Read + 'b>>,
+}
+
+impl<'a, 'b> Machine<'a, 'b> {
+    pub fn new(program: Program, num_of_cells: usize, eof: EofPolicy) -> Self {
+        Self {
+            program,
+            instruction_pointer: 0,
+            data_pointer: 0,
+            data: vec![0; num_of_cells],
+            steps: 0,
+            eof,
+            sink: None,
+            source: None,
+        }
+    }
+
+    /// The index of the character the next operation was compiled from (or the length of the code, once the program has finished)
+    pub fn position(&self) -> usize {
+        self.program
+            .instructions
+            .get(self.instruction_pointer)
+            .map_or(self.program.code_len, |instruction| instruction.position)
+    }
+
+    /// If this returns `Ok(None)`, the end of the program was reached
+    pub fn run_step(&mut self) -> InterpreterResult<Option<()>> {
+        let Some(instruction) = self.program.instructions.get(self.instruction_pointer) else {
+            return Ok(None);
+        };
+        self.steps += 1;
+
+        let cell = &mut self.data[self.data_pointer];
+        match instruction.op {
+            Op::Add(value) => *cell = cell.wrapping_add(value),
+            Op::Move(offset) => {
+                self.data_pointer = (self.data_pointer as isize + offset)
+                    .rem_euclid(self.data.len() as isize)
+                    as usize
+            }
+            Op::Output => {
+                let byte = *cell;
+                match &mut self.sink {
+                    Some(writable) => writable.write_all(&[byte])?,
+                    None => {
+                        let mut stdout = io::stdout();
+                        stdout.write_all(&[byte])?;
+                        stdout.flush()?
+                    }
+                }
+            }
+            Op::Input => {
+                let mut buf = [0u8];
+                let read = match &mut self.source {
+                    Some(readable) => readable.read(&mut buf)?,
+                    None => io::stdin().read(&mut buf)?,
+                };
+
+                let cell = &mut self.data[self.data_pointer];
+                match (read, self.eof) {
+                    (1.., _) => *cell = buf[0],
+                    (_, EofPolicy::Unchanged) => (),
+                    (_, EofPolicy::Zero) => *cell = 0,
+                    (_, EofPolicy::Max) => *cell = u8::MAX,
+                    (_, EofPolicy::Error) => return Err(InterpreterError::EndOfInput),
+                }
+            }
+            Op::Clear => *cell = 0,
+            Op::JumpIfZero(end) if *cell == 0 => self.instruction_pointer = end,
+            Op::JumpIfNonZero(start) if *cell != 0 => self.instruction_pointer = start,
+            Op::JumpIfZero(_) | Op::JumpIfNonZero(_) => (),
+        }
+
+        self.instruction_pointer += 1;
+
+        Ok(Some(()))
+    }
+
+    pub fn run_to_end(&mut self) -> InterpreterResult<()> {
+        while self.run_step()?.is_some() {}
+        Ok(())
+    }
+
+    pub fn set_sink<W>(&mut self, sink: &'a mut W)
+    where
+        W: io::Write,
+    {
+        self.sink = Some(sink);
+    }
+
+    pub fn set_source<R>(&mut self, source: &'b mut R)
+    where
+        R: io::Read,
+    {
+        self.source = Some(Box::new(source));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compile() {
+        let code = "+++--[-]>><<<[.,]".chars().collect::<Vec<_>>();
+        let program = Program::compile(&code).unwrap();
+
+        assert_eq!(
+            program
+                .instructions
+                .iter()
+                .map(|instruction| (instruction.op, instruction.position))
+                .collect::<Vec<_>>(),
+            [
+                (Op::Add(1), 0),
+                (Op::Clear, 5),
+                (Op::Move(-1), 8),
+                (Op::JumpIfZero(6), 13),
+                (Op::Output, 14),
+                (Op::Input, 15),
+                (Op::JumpIfNonZero(3), 16),
+            ]
+        );
+        assert!(matches!(
+            Program::compile(&['[']),
+            Err(InterpreterError::UnmatchedLoop)
+        ));
+    }
+
+    #[test]
+    fn hello_world() {
+        const PROGRAM: &str = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.";
+
+        let mut output = Vec::new();
+        let mut machine = Machine::new(
+            Program::compile(&PROGRAM.chars().collect::<Vec<_>>()).unwrap(),
+            DEFAULT_CELL_SIZE,
+            EofPolicy::default(),
+        );
+        machine.set_sink(&mut output);
+        machine.run_to_end().unwrap();
+        drop(machine);
+
+        assert_eq!(output, b"Hello World!\n");
+    }
+}
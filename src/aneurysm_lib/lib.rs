@@ -1 +1,15 @@
+pub mod analysis;
+pub mod config;
+pub mod coverage;
+pub mod difftest;
+pub mod formatter;
+pub mod generator;
 pub mod interpreter;
+pub mod ir;
+pub mod lint;
+pub mod minifier;
+pub mod preprocessor;
+pub mod source;
+pub mod tape;
+pub mod tokens;
+pub mod trace;
@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+
+use displaydoc::Display;
+
+use crate::analysis::loop_offset;
+use crate::interpreter::*;
+use crate::source::*;
+
+#[derive(Display, Clone, Debug, PartialEq, Eq)]
+pub enum LintKind {
+    /// This loop never terminates once it is entered, since it neither moves the data pointer nor changes the current cell
+    InfiniteLoop,
+    /// This loop changes cells, but moves the data pointer by {0} cell(s) on every iteration
+    UnbalancedLoop(isize),
+    /// `{0}` is immediately cancelled out by `{1}`
+    CancellingInstructions(char, char),
+    /// This code is unreachable, since the loop at {0} is always entered and never terminates
+    UnreachableCode(Position),
+    /// The data pointer is moved left of cell 0 (it will wrap around to the end of the tape)
+    LeftOfOrigin,
+}
+
+impl LintKind {
+    /// A short, machine-readable name for this kind of lint
+    pub fn name(&self) -> &'static str {
+        match self {
+            LintKind::InfiniteLoop => "infinite-loop",
+            LintKind::UnbalancedLoop(_) => "unbalanced-loop",
+            LintKind::CancellingInstructions(..) => "cancelling-instructions",
+            LintKind::UnreachableCode(_) => "unreachable-code",
+            LintKind::LeftOfOrigin => "left-of-origin",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lint {
+    pub kind: LintKind,
+    /// The index of the character the lint refers to
+    pub index: usize,
+    /// The position of the character the lint refers to
+    pub position: Position,
+}
+
+fn is_instruction(c: char) -> bool {
+    matches!(c, '>' | '<' | '+' | '-' | '.' | ',' | '[' | ']')
+}
+
+/// A loop never terminates once entered if it doesn't move, doesn't read input and its additions cancel out
+fn is_infinite_loop(code: &[char], loops: &Loops, start: usize) -> bool {
+    let end = *loops.get_by_left(&start).unwrap();
+    let mut change: i64 = 0;
+
+    for c in &code[start + 1..end] {
+        match c {
+            '+' => change += 1,
+            '-' => change -= 1,
+            '<' | '>' | ',' | '[' => return false,
+            _ => (),
+        }
+    }
+
+    change.rem_euclid(256) == 0
+}
+
+/// What we know about the program's state when executing it statically
+struct KnownState {
+    data_pointer: isize,
+    /// Cells whose value is known (or known to be unknown)
+    cells: HashMap<isize, Option<u8>>,
+    /// The value of every cell not in `cells`
+    default: Option<u8>,
+}
+
+impl KnownState {
+    fn cell(&self) -> Option<u8> {
+        *self.cells.get(&self.data_pointer).unwrap_or(&self.default)
+    }
+
+    fn set_cell(&mut self, value: Option<u8>) {
+        self.cells.insert(self.data_pointer, value);
+    }
+}
+
+/// Execute as much of the program as can be determined statically, looking for moves left of cell 0 and guaranteed infinite loops
+fn lint_execution(
+    code: &[char],
+    loops: &Loops,
+    positions: &[Position],
+    lint: &mut impl FnMut(LintKind, usize),
+) {
+    let mut state = KnownState {
+        data_pointer: 0,
+        cells: HashMap::new(),
+        default: Some(0),
+    };
+    let mut index = 0;
+
+    while index < code.len() {
+        match code[index] {
+            '>' => state.data_pointer += 1,
+            '<' => {
+                state.data_pointer -= 1;
+                if state.data_pointer == -1 {
+                    lint(LintKind::LeftOfOrigin, index)
+                }
+            }
+            '+' => state.set_cell(state.cell().map(|value| value.wrapping_add(1))),
+            '-' => state.set_cell(state.cell().map(|value| value.wrapping_sub(1))),
+            ',' => state.set_cell(None),
+            '[' => {
+                let end = *loops.get_by_left(&index).unwrap();
+
+                match state.cell() {
+                    // the loop is skipped
+                    Some(0) => (),
+                    Some(_) if is_infinite_loop(code, loops, index) => {
+                        if let Some(unreachable) =
+                            (end + 1..code.len()).find(|&i| is_instruction(code[i]))
+                        {
+                            lint(LintKind::UnreachableCode(positions[index]), unreachable)
+                        }
+                        return;
+                    }
+                    _ => match loop_offset(code, loops, index) {
+                        // we don't know which cells were changed by the loop, only that the current one is now 0
+                        Some(0) => {
+                            state.cells.clear();
+                            state.default = None;
+                            state.set_cell(Some(0));
+                        }
+                        // we can't know where the data pointer is anymore
+                        _ => return,
+                    },
+                }
+
+                index = end;
+            }
+            _ => (),
+        }
+
+        index += 1;
+    }
+}
+
+/// Look for likely bugs in a Brainf**k program
+///
+/// The lints are sorted by the index of the character they refer to.
+/// Returns [`InterpreterError::UnmatchedLoop`] if the program contains unmatched loop brackets
+pub fn lint(code: &str) -> InterpreterResult<Vec<Lint>> {
+    let code = code.chars().collect::<Vec<char>>();
+    let loops = Interpreter::get_loop(&code)?;
+    let positions = positions(&code);
+
+    let mut lints: Vec<Lint> = Vec::new();
+    let mut push_lint = |kind: LintKind, index: usize| {
+        lints.push(Lint {
+            kind,
+            index,
+            position: positions[index],
+        })
+    };
+
+    // lints concerning the structure of each loop
+    for (&start, _) in loops.iter() {
+        if is_infinite_loop(&code, &loops, start) {
+            push_lint(LintKind::InfiniteLoop, start);
+        } else if let Some(offset) = loop_offset(&code, &loops, start) {
+            let end = *loops.get_by_left(&start).unwrap();
+            let changes_cells = code[start + 1..end]
+                .iter()
+                .any(|c| matches!(c, '+' | '-' | ','));
+
+            if offset != 0 && changes_cells {
+                push_lint(LintKind::UnbalancedLoop(offset), start);
+            }
+        }
+    }
+
+    // instructions that cancel each other out, ignoring comments
+    let mut stack: Vec<usize> = Vec::new();
+    for (index, &c) in code.iter().enumerate() {
+        match c {
+            '+' | '-' | '<' | '>' => match stack.last() {
+                Some(&last)
+                    if matches!(
+                        (code[last], c),
+                        ('+', '-') | ('-', '+') | ('<', '>') | ('>', '<')
+                    ) =>
+                {
+                    push_lint(LintKind::CancellingInstructions(code[last], c), last);
+                    stack.pop();
+                }
+                _ => stack.push(index),
+            },
+            c if is_instruction(c) => stack.clear(),
+            _ => (),
+        }
+    }
+
+    lint_execution(&code, &loops, &positions, &mut push_lint);
+
+    lints.sort_by_key(|lint| lint.index);
+
+    Ok(lints)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint_names(code: &str) -> Vec<&'static str> {
+        lint(code)
+            .unwrap()
+            .iter()
+            .map(|lint| lint.kind.name())
+            .collect()
+    }
+
+    #[test]
+    fn infinite_loops() {
+        assert_eq!(lint_names("+[]"), vec!["infinite-loop"]);
+        assert_eq!(
+            lint_names(",[+-]"),
+            vec!["infinite-loop", "cancelling-instructions"]
+        );
+        // this one terminates eventually
+        assert!(lint_names(",[++.]").is_empty());
+    }
+
+    #[test]
+    fn unbalanced_loops() {
+        assert_eq!(lint_names(",[->+]"), vec!["unbalanced-loop"]);
+        // scanning loops are fine
+        assert!(lint_names(",[>]").is_empty());
+    }
+
+    #[test]
+    /// Code after a loop that is always entered and never exited can never run
+    fn unreachable_code() {
+        let lints = lint("+\n[+-]\n.").unwrap();
+        let unreachable = lints.last().unwrap();
+
+        assert_eq!(
+            unreachable.kind,
+            LintKind::UnreachableCode(Position { line: 2, column: 1 })
+        );
+        assert_eq!(unreachable.position, Position { line: 3, column: 1 });
+    }
+
+    #[test]
+    fn left_of_origin() {
+        let lints = lint(">>[-]<<<").unwrap();
+
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].kind, LintKind::LeftOfOrigin);
+        assert_eq!(lints[0].index, 7);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::interpreter::*;
+
+/// Returns the instruction that undoes `instruction`, if there is one
+fn inverse(instruction: char) -> Option<char> {
+    match instruction {
+        '+' => Some('-'),
+        '-' => Some('+'),
+        '>' => Some('<'),
+        '<' => Some('>'),
+        _ => None,
+    }
+}
+
+/// Cancel adjacent instructions that undo each other (`+-`, `-+`, `<>` and `><`)
+fn cancel_pairs(code: &[char]) -> Vec<char> {
+    let mut output: Vec<char> = Vec::with_capacity(code.len());
+
+    for &instruction in code {
+        match output.last() {
+            Some(&last) if inverse(last) == Some(instruction) => {
+                output.pop();
+            }
+            _ => output.push(instruction),
+        }
+    }
+
+    output
+}
+
+/// Remove loops that can never be entered, since the current cell is always zero when they are reached
+///
+/// This is the case for loops at the very start of the program and loops that directly follow another loop.
+/// The former assumes that the program starts on a zeroed tape, which isn't true with a loaded tape image (see [`crate::tape`])
+fn remove_dead_loops(code: &[char]) -> Vec<char> {
+    let mut output: Vec<char> = Vec::with_capacity(code.len());
+    let mut index = 0;
+
+    while index < code.len() {
+        if code[index] == '[' && matches!(output.last(), None | Some(']')) {
+            // skip everything up to (and including) the matching bracket
+            let mut depth = 0;
+            loop {
+                match code[index] {
+                    '[' => depth += 1,
+                    ']' => depth -= 1,
+                    _ => (),
+                }
+                index += 1;
+
+                if depth == 0 {
+                    break;
+                }
+            }
+        } else {
+            output.push(code[index]);
+            index += 1;
+        }
+    }
+
+    output
+}
+
+/// Produce the shortest program equivalent to `code` that this module can find
+///
+/// Comments are stripped, adjacent `+-`/`<>` pairs are cancelled and loops that can never execute are removed, until none of those changes anything.
+/// The program is assumed to start on a zeroed tape.
+/// Returns [`InterpreterError::UnmatchedLoop`] if the program contains unmatched loop brackets
+pub fn minify(code: &str) -> InterpreterResult<String> {
+    let mut code = code.chars().collect::<Vec<char>>();
+    Interpreter::remove_comments(&mut code, Dialect::Brainfuck);
+    Interpreter::get_loop(&code)?;
+
+    loop {
+        let minified = remove_dead_loops(&cancel_pairs(&code));
+
+        if minified.len() == code.len() {
+            break;
+        }
+        code = minified;
+    }
+
+    Ok(code.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancel_instructions() {
+        assert_eq!(minify("+>+-<->>+<<").unwrap(), ">>+<<");
+        assert_eq!(minify("++<><>--.").unwrap(), ".");
+    }
+
+    #[test]
+    /// Loops at the start of the program or right after another loop never run
+    fn dead_loops() {
+        assert_eq!(minify("[this is a comment.]+[-][>.<]").unwrap(), "+[-]");
+        // removing the pair in the middle makes the second loop dead
+        assert_eq!(minify("+[-]+-[.[.]]").unwrap(), "+[-]");
+    }
+
+    #[test]
+    /// The minified program must still do exactly the same thing
+    fn equivalent() {
+        const PROGRAM: &str = "[comment] ++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.";
+
+        let minified = minify(PROGRAM).unwrap();
+        assert!(minified.len() < PROGRAM.len());
+
+        let mut output: Vec<u8> = Vec::new();
+        let mut interpreter = Interpreter::new(minified, InterpreterOptions::release()).unwrap();
+        interpreter.set_sink(&mut output);
+        interpreter.run_to_end().unwrap();
+
+        assert_eq!(std::str::from_utf8(&output).unwrap(), "Hello World!\n")
+    }
+}
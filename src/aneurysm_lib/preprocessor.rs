@@ -0,0 +1,777 @@
+//! An optional preprocessing stage that runs before the code is handed to [`Interpreter::new`](crate::interpreter::Interpreter::new)
+//!
+//! It supports the following constructs:
+//! - `#include "file.bf"` pastes the contents of another file (relative to the current one)
+//! - `#define NAME(a, b) body` and `#define NAME body` define a macro, which is expanded wherever `NAME(x, y)` (or just `NAME`) appears
+//! - `+*10` repeats an instruction (or a macro invocation) 10 times
+//! - `#cell name 5` names cell 5, and `%name` moves the data pointer to it (only if the data pointer's position is known at that point)
+//!
+//! Directives must be placed at the start of a line. Every character of the output keeps track of where it came from,
+//! so that errors (and the debugger) can point to the original source files
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use displaydoc::Display;
+
+use crate::source::*;
+use crate::tokens::TokenSet;
+
+/// The longest the expanded code may get, so that a large repeat count can't make the preprocessor run out of memory
+pub const MAX_OUTPUT_LEN: usize = 1 << 22;
+
+/// A character along with where it was originally found
+#[derive(Clone, Copy, Debug)]
+struct SourceChar {
+    c: char,
+    file: usize,
+    position: Position,
+}
+
+struct Macro {
+    params: Vec<String>,
+    body: Vec<SourceChar>,
+}
+
+/// The state of the data pointer tracking when a loop was entered
+struct LoopEntry {
+    data_pointer: Option<isize>,
+    uses_aliases: bool,
+    location: SourceChar,
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn skip_whitespace(text: &[SourceChar], mut index: usize) -> usize {
+    while index < text.len() && text[index].c.is_whitespace() && text[index].c != '\n' {
+        index += 1;
+    }
+    index
+}
+
+/// Reads an identifier starting at `index`, returning it and the index right after it
+fn read_ident(text: &[SourceChar], index: usize) -> Option<(String, usize)> {
+    if index >= text.len() || !is_ident_start(text[index].c) {
+        return None;
+    }
+
+    let end = (index..text.len())
+        .find(|&i| !is_ident_char(text[i].c))
+        .unwrap_or(text.len());
+
+    Some((text[index..end].iter().map(|sc| sc.c).collect(), end))
+}
+
+fn trim(mut text: &[SourceChar]) -> &[SourceChar] {
+    while let Some((first, rest)) = text.split_first() {
+        if !first.c.is_whitespace() {
+            break;
+        }
+        text = rest;
+    }
+    while let Some((last, rest)) = text.split_last() {
+        if !last.c.is_whitespace() {
+            break;
+        }
+        text = rest;
+    }
+    text
+}
+
+struct Preprocessor {
+    files: Vec<PathBuf>,
+    include_stack: Vec<PathBuf>,
+    macros: HashMap<String, Macro>,
+    expanding: Vec<String>,
+    aliases: HashMap<String, isize>,
+
+    output: Vec<SourceChar>,
+    /// Where the data pointer is relative to cell 0, if that is known at this point of the program
+    data_pointer: Option<isize>,
+    loops: Vec<LoopEntry>,
+}
+
+impl Preprocessor {
+    fn new() -> Self {
+        Self {
+            files: Vec::new(),
+            include_stack: Vec::new(),
+            macros: HashMap::new(),
+            expanding: Vec::new(),
+            aliases: HashMap::new(),
+
+            output: Vec::new(),
+            data_pointer: Some(0),
+            loops: Vec::new(),
+        }
+    }
+
+    fn error(&self, at: SourceChar, kind: PreprocessErrorKind) -> PreprocessError {
+        PreprocessError {
+            location: Some(SourceLocation {
+                file: self.files[at.file].clone(),
+                position: at.position,
+            }),
+            kind,
+        }
+    }
+
+    fn process_code(
+        &mut self,
+        code: &str,
+        path: &Path,
+        included_from: Option<SourceChar>,
+    ) -> Result<(), PreprocessError> {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if self.include_stack.contains(&canonical) {
+            let kind = PreprocessErrorKind::RecursiveInclude(path.display().to_string());
+            return Err(match included_from {
+                Some(at) => self.error(at, kind),
+                None => PreprocessError {
+                    location: None,
+                    kind,
+                },
+            });
+        }
+
+        let file = self.files.len();
+        self.files.push(path.to_path_buf());
+
+        let chars = code.chars().collect::<Vec<char>>();
+        let text = chars
+            .iter()
+            .zip(positions(&chars))
+            .map(|(&c, position)| SourceChar { c, file, position })
+            .collect::<Vec<SourceChar>>();
+
+        self.include_stack.push(canonical);
+        self.process(&text, true)?;
+        self.include_stack.pop();
+
+        Ok(())
+    }
+
+    fn process_file(
+        &mut self,
+        path: &Path,
+        included_from: Option<SourceChar>,
+    ) -> Result<(), PreprocessError> {
+        match fs::read_to_string(path) {
+            Ok(code) => self.process_code(&code, path, included_from),
+            Err(err) => {
+                let kind = PreprocessErrorKind::IOError(path.display().to_string(), err);
+                Err(match included_from {
+                    Some(at) => self.error(at, kind),
+                    None => PreprocessError {
+                        location: None,
+                        kind,
+                    },
+                })
+            }
+        }
+    }
+
+    /// Process some text, writing the result to `self.output`. Directives are only allowed in files, not in macro bodies
+    fn process(
+        &mut self,
+        text: &[SourceChar],
+        allow_directives: bool,
+    ) -> Result<(), PreprocessError> {
+        let mut index = 0;
+        let mut line_start = true;
+
+        while index < text.len() {
+            let current = text[index];
+
+            if allow_directives && line_start {
+                let start = skip_whitespace(text, index);
+                if start < text.len() && text[start].c == '#' {
+                    let end = (start..text.len())
+                        .find(|&i| text[i].c == '\n')
+                        .unwrap_or(text.len());
+
+                    if self.directive(&text[start..end])? {
+                        index = end;
+                        line_start = false;
+                        continue;
+                    }
+                }
+            }
+            line_start = current.c == '\n';
+
+            // macro invocations
+            if is_ident_start(current.c) && (index == 0 || !is_ident_char(text[index - 1].c)) {
+                let (name, end) = read_ident(text, index).unwrap();
+
+                if self.macros.contains_key(&name) {
+                    let (expansion, end) = self.expand(text, index, &name, end)?;
+                    let (count, end) =
+                        repeat_count(text, end).map_err(|kind| self.error(text[end], kind))?;
+
+                    self.expanding.push(name);
+                    for _ in 0..count {
+                        self.process(&expansion, false)?;
+                    }
+                    self.expanding.pop();
+
+                    index = end;
+                } else {
+                    // just a comment
+                    for &sc in &text[index..end] {
+                        self.emit(sc)?;
+                    }
+                    index = end;
+                }
+                continue;
+            }
+
+            // cell aliases (`%` isn't an instruction in any dialect, unlike `@`)
+            if current.c == '%' {
+                if let Some((name, end)) = read_ident(text, index + 1) {
+                    self.move_to_alias(current, &name)?;
+                    index = end;
+                    continue;
+                }
+            }
+
+            // repeated instructions
+            if matches!(current.c, '+' | '-' | '<' | '>' | '.' | ',') {
+                let (count, end) = repeat_count(text, index + 1)
+                    .map_err(|kind| self.error(text[index + 1], kind))?;
+                for _ in 0..count {
+                    self.emit(current)?;
+                }
+                index = end;
+                continue;
+            }
+
+            self.emit(current)?;
+            index += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Handle a directive line (starting with `#`). Returns `false` if this isn't a directive, but a plain comment
+    fn directive(&mut self, line: &[SourceChar]) -> Result<bool, PreprocessError> {
+        let Some((keyword, index)) = read_ident(line, 1) else {
+            return Ok(false);
+        };
+        let invalid = |this: &Self| {
+            this.error(
+                line[0],
+                PreprocessErrorKind::InvalidDirective(line.iter().map(|sc| sc.c).collect()),
+            )
+        };
+
+        match keyword.as_str() {
+            "include" => {
+                let argument = trim(&line[index..]);
+                let path: String = argument.iter().map(|sc| sc.c).collect();
+
+                let Some(path) = path
+                    .strip_prefix('"')
+                    .and_then(|path| path.strip_suffix('"'))
+                else {
+                    return Err(invalid(self));
+                };
+
+                // includes are relative to the file that includes them
+                let path = match self.files[line[0].file].parent() {
+                    Some(parent) => parent.join(path),
+                    None => PathBuf::from(path),
+                };
+                self.process_file(&path, Some(line[0]))?;
+            }
+            "define" => {
+                let Some((name, mut index)) = read_ident(line, skip_whitespace(line, index)) else {
+                    return Err(invalid(self));
+                };
+
+                let mut params = Vec::new();
+                if index < line.len() && line[index].c == '(' {
+                    let Some(end) = (index..line.len()).find(|&i| line[i].c == ')') else {
+                        return Err(invalid(self));
+                    };
+
+                    for param in line[index + 1..end].split(|sc| sc.c == ',') {
+                        let param = trim(param);
+                        match read_ident(param, 0) {
+                            Some((param, end)) if end == param.len() => params.push(param),
+                            _ => return Err(invalid(self)),
+                        }
+                    }
+                    index = end + 1;
+                }
+
+                if self.macros.contains_key(&name) {
+                    log::warn!(
+                        "{}:{}: redefining macro \"{}\"",
+                        self.files[line[0].file].display(),
+                        line[0].position,
+                        name
+                    );
+                }
+
+                self.macros.insert(
+                    name,
+                    Macro {
+                        params,
+                        body: trim(&line[index..]).to_vec(),
+                    },
+                );
+            }
+            "cell" => {
+                let Some((name, index)) = read_ident(line, skip_whitespace(line, index)) else {
+                    return Err(invalid(self));
+                };
+                let cell: String = trim(&line[index..]).iter().map(|sc| sc.c).collect();
+                let Ok(cell) = cell.parse::<usize>() else {
+                    return Err(invalid(self));
+                };
+
+                self.aliases.insert(name, cell as isize);
+            }
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
+    /// Expand the invocation of macro `name` found at `start`, returning the expansion and the index right after the invocation
+    ///
+    /// The macro invocations in the arguments are expanded before they are substituted, so that `TWICE(TWICE(+))` isn't mistaken for recursion
+    fn expand(
+        &mut self,
+        text: &[SourceChar],
+        start: usize,
+        name: &str,
+        mut end: usize,
+    ) -> Result<(Vec<SourceChar>, usize), PreprocessError> {
+        if self.expanding.iter().any(|expanding| expanding == name) {
+            return Err(self.error(
+                text[start],
+                PreprocessErrorKind::RecursiveMacro(name.to_string()),
+            ));
+        }
+
+        let param_count = self.macros[name].params.len();
+        if param_count == 0 {
+            return Ok((self.macros[name].body.clone(), end));
+        }
+
+        // parse the arguments, which are separated by commas and may contain parentheses
+        if end >= text.len() || text[end].c != '(' {
+            return Err(self.error(
+                text[start],
+                PreprocessErrorKind::MissingArguments(name.to_string()),
+            ));
+        }
+
+        let mut args: Vec<Vec<SourceChar>> = Vec::new();
+        let mut arg_start = end + 1;
+        let mut depth = 0;
+        end += 1;
+
+        loop {
+            if end >= text.len() {
+                return Err(self.error(
+                    text[start],
+                    PreprocessErrorKind::UnclosedInvocation(name.to_string()),
+                ));
+            }
+
+            match text[end].c {
+                '(' => depth += 1,
+                ')' if depth > 0 => depth -= 1,
+                ')' => {
+                    args.push(self.expand_macros(trim(&text[arg_start..end]))?);
+                    break;
+                }
+                ',' if depth == 0 => {
+                    args.push(self.expand_macros(trim(&text[arg_start..end]))?);
+                    arg_start = end + 1;
+                }
+                _ => (),
+            }
+            end += 1;
+        }
+
+        if args.len() != param_count {
+            return Err(self.error(
+                text[start],
+                PreprocessErrorKind::WrongArgumentCount {
+                    name: name.to_string(),
+                    expected: param_count,
+                    found: args.len(),
+                },
+            ));
+        }
+
+        let macro_ = &self.macros[name];
+
+        // replace each parameter with the corresponding argument
+        let mut expansion = Vec::new();
+        let mut index = 0;
+        while index < macro_.body.len() {
+            if index == 0 || !is_ident_char(macro_.body[index - 1].c) {
+                if let Some((ident, ident_end)) = read_ident(&macro_.body, index) {
+                    match macro_.params.iter().position(|param| *param == ident) {
+                        Some(param) => expansion.extend_from_slice(&args[param]),
+                        None => expansion.extend_from_slice(&macro_.body[index..ident_end]),
+                    }
+                    index = ident_end;
+                    continue;
+                }
+            }
+
+            expansion.push(macro_.body[index]);
+            index += 1;
+        }
+
+        Ok((expansion, end + 1))
+    }
+
+    /// Expand the macro invocations found in `text`, leaving everything else (like repeat counts and cell aliases) as it is
+    fn expand_macros(&mut self, text: &[SourceChar]) -> Result<Vec<SourceChar>, PreprocessError> {
+        let mut output = Vec::new();
+        let mut index = 0;
+
+        while index < text.len() {
+            if is_ident_start(text[index].c) && (index == 0 || !is_ident_char(text[index - 1].c)) {
+                let (name, end) = read_ident(text, index).unwrap();
+
+                if self.macros.contains_key(&name) {
+                    let (expansion, end) = self.expand(text, index, &name, end)?;
+                    let (count, end) =
+                        repeat_count(text, end).map_err(|kind| self.error(text[end], kind))?;
+
+                    self.expanding.push(name);
+                    let expansion = self.expand_macros(&expansion)?;
+                    self.expanding.pop();
+
+                    if expansion.len().saturating_mul(count) > MAX_OUTPUT_LEN - output.len() {
+                        return Err(self.error(
+                            text[index],
+                            PreprocessErrorKind::OutputTooLarge(MAX_OUTPUT_LEN),
+                        ));
+                    }
+                    for _ in 0..count {
+                        output.extend_from_slice(&expansion);
+                    }
+                    index = end;
+                } else {
+                    output.extend_from_slice(&text[index..end]);
+                    index = end;
+                }
+                continue;
+            }
+
+            output.push(text[index]);
+            index += 1;
+        }
+
+        Ok(output)
+    }
+
+    fn move_to_alias(&mut self, at: SourceChar, name: &str) -> Result<(), PreprocessError> {
+        let Some(&cell) = self.aliases.get(name) else {
+            return Err(self.error(at, PreprocessErrorKind::UnknownAlias(name.to_string())));
+        };
+        let Some(data_pointer) = self.data_pointer else {
+            return Err(self.error(at, PreprocessErrorKind::UnknownPosition(name.to_string())));
+        };
+
+        self.loops
+            .iter_mut()
+            .for_each(|entry| entry.uses_aliases = true);
+
+        let instruction = if cell >= data_pointer { '>' } else { '<' };
+        for _ in 0..(cell - data_pointer).unsigned_abs() {
+            self.emit(SourceChar {
+                c: instruction,
+                ..at
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Write a character to the output, keeping track of the data pointer
+    fn emit(&mut self, sc: SourceChar) -> Result<(), PreprocessError> {
+        match sc.c {
+            '>' => self.data_pointer = self.data_pointer.map(|pointer| pointer + 1),
+            '<' => self.data_pointer = self.data_pointer.map(|pointer| pointer - 1),
+            '[' => self.loops.push(LoopEntry {
+                data_pointer: self.data_pointer,
+                uses_aliases: false,
+                location: sc,
+            }),
+            ']' => {
+                // unmatched brackets are reported by the interpreter itself
+                if let Some(entry) = self.loops.pop() {
+                    if entry.data_pointer != self.data_pointer {
+                        // after the first iteration, the data pointer won't be where the aliases expect it to be
+                        if entry.uses_aliases {
+                            return Err(self
+                                .error(entry.location, PreprocessErrorKind::UnbalancedAliasLoop));
+                        }
+                        self.data_pointer = None;
+                    }
+                }
+            }
+            _ => (),
+        }
+
+        if self.output.len() >= MAX_OUTPUT_LEN {
+            return Err(self.error(sc, PreprocessErrorKind::OutputTooLarge(MAX_OUTPUT_LEN)));
+        }
+        self.output.push(sc);
+
+        Ok(())
+    }
+
+    fn finish(self) -> Preprocessed {
+        Preprocessed {
+            code: self.output.iter().map(|sc| sc.c).collect(),
+            source_map: SourceMap {
+                locations: self
+                    .output
+                    .iter()
+                    .map(|sc| (sc.file, sc.position))
+                    .collect(),
+                files: self.files,
+            },
+        }
+    }
+}
+
+/// Checks whether a `*N` repeat count is found at `index`, returning the count (1 if there isn't one) and the index right after it
+fn repeat_count(text: &[SourceChar], index: usize) -> Result<(usize, usize), PreprocessErrorKind> {
+    if index + 1 < text.len() && text[index].c == '*' && text[index + 1].c.is_ascii_digit() {
+        let end = (index + 1..text.len())
+            .find(|&i| !text[i].c.is_ascii_digit())
+            .unwrap_or(text.len());
+        let count: String = text[index + 1..end].iter().map(|sc| sc.c).collect();
+
+        match count.parse() {
+            Ok(count) if count <= MAX_OUTPUT_LEN => Ok((count, end)),
+            _ => Err(PreprocessErrorKind::RepeatCountTooLarge(count)),
+        }
+    } else {
+        Ok((1, index))
+    }
+}
+
+/// The output of the preprocessor
+#[derive(Debug)]
+pub struct Preprocessed {
+    /// The expanded code, ready to be passed to [`Interpreter::new`](crate::interpreter::Interpreter::new)
+    pub code: String,
+    /// Maps each character of `code` to where it was originally found
+    pub source_map: SourceMap,
+}
+
+impl Preprocessed {
+    /// Maps each instruction of `code` back to where it was originally found, once the tokens of `tokens` (if any) are translated into instructions
+    pub fn instruction_map(&self, tokens: Option<&TokenSet>) -> SourceMap {
+        match tokens {
+            Some(tokens) => tokens.map_through(&self.code, &self.source_map),
+            None => self.source_map.clone(),
+        }
+    }
+}
+
+/// Preprocess the file at `path`
+pub fn preprocess_file<P>(path: P) -> Result<Preprocessed, PreprocessError>
+where
+    P: AsRef<Path>,
+{
+    let mut preprocessor = Preprocessor::new();
+    preprocessor.process_file(path.as_ref(), None)?;
+
+    Ok(preprocessor.finish())
+}
+
+/// Preprocess `code`, as if it was read from `path` (which is used to resolve includes and report errors)
+pub fn preprocess<P>(code: &str, path: P) -> Result<Preprocessed, PreprocessError>
+where
+    P: AsRef<Path>,
+{
+    let mut preprocessor = Preprocessor::new();
+    preprocessor.process_code(code, path.as_ref(), None)?;
+
+    Ok(preprocessor.finish())
+}
+
+#[derive(thiserror::Error, Debug)]
+pub struct PreprocessError {
+    /// Where the error occured (unless the file couldn't be read in the first place)
+    pub location: Option<SourceLocation>,
+    #[source]
+    pub kind: PreprocessErrorKind,
+}
+
+impl fmt::Display for PreprocessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "{}: {}", location, self.kind),
+            None => write!(f, "{}", self.kind),
+        }
+    }
+}
+
+#[derive(Display, thiserror::Error, Debug)]
+pub enum PreprocessErrorKind {
+    /// Couldn't read file "{0}": {1}
+    IOError(String, #[source] io::Error),
+    /// File "{0}" includes itself
+    RecursiveInclude(String),
+    /// Invalid directive "{0}"
+    InvalidDirective(String),
+    /// Macro "{0}" expands to itself
+    RecursiveMacro(String),
+    /// Macro "{0}" expects arguments
+    MissingArguments(String),
+    /// The arguments of macro "{0}" are never closed
+    UnclosedInvocation(String),
+    /// Macro "{name}" expects {expected} argument(s), found {found}
+    WrongArgumentCount {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    /// There is no cell named "{0}"
+    UnknownAlias(String),
+    /// Can't move to cell "{0}", since the data pointer's position isn't known here (probably because of an unbalanced loop)
+    UnknownPosition(String),
+    /// This loop uses cell aliases, but doesn't leave the data pointer where it found it
+    UnbalancedAliasLoop,
+    /// Repeat count {0} is too large
+    RepeatCountTooLarge(String),
+    /// The expanded code is longer than {0} characters
+    OutputTooLarge(usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn repeat_and_macros() {
+        const PROGRAM: &str =
+            "#define CLEAR [-]\n#define MOVE(from, to) from[-to+from]\n+*3 MOVE(>*2, <*2) CLEAR*2";
+
+        let preprocessed = preprocess(PROGRAM, "test.bf").unwrap();
+        assert_eq!(preprocessed.code, "\n\n+++ >>[-<<+>>] [-][-]");
+    }
+
+    #[test]
+    fn nested_macros() {
+        const PROGRAM: &str = "#define TWICE(x) x x\n#define UP +\nTWICE(TWICE(UP*2))";
+
+        let preprocessed = preprocess(PROGRAM, "test.bf").unwrap();
+        assert_eq!(preprocessed.code, "\n\n++ ++ ++ ++");
+
+        // a macro that really invokes itself is still an error, even from an argument
+        let err = preprocess("#define LOOP(x) LOOP(x)\nLOOP(LOOP(+))", "test.bf").unwrap_err();
+        assert!(matches!(err.kind, PreprocessErrorKind::RecursiveMacro(_)));
+    }
+
+    #[test]
+    fn repeat_limits() {
+        let err = preprocess("+*99999999999999999999", "test.bf").unwrap_err();
+        assert!(matches!(
+            err.kind,
+            PreprocessErrorKind::RepeatCountTooLarge(_)
+        ));
+        assert_eq!(
+            err.location.unwrap().position,
+            Position { line: 1, column: 2 }
+        );
+
+        // each count is small enough, but the expansion isn't
+        let err = preprocess("#define A +*4000\nA*4000", "test.bf").unwrap_err();
+        assert!(matches!(err.kind, PreprocessErrorKind::OutputTooLarge(_)));
+    }
+
+    #[test]
+    fn cell_aliases() {
+        const PROGRAM: &str =
+            "#cell counter 3\n#cell result 1\n%counter+++[-%result++%counter]%result.";
+
+        let preprocessed = preprocess(PROGRAM, "test.bf").unwrap();
+        assert_eq!(preprocessed.code, "\n\n>>>+++[-<<++>>]<<.");
+
+        // the data pointer's position isn't known after a loop that isn't balanced
+        let err = preprocess("#cell x 0\n[>]%x", "test.bf").unwrap_err();
+        assert!(matches!(err.kind, PreprocessErrorKind::UnknownPosition(_)));
+        assert_eq!(
+            err.location.unwrap().position,
+            Position { line: 2, column: 4 }
+        );
+    }
+
+    #[test]
+    /// Tokens should be mapped back to where they were found, not to their position in the preprocessed code
+    fn tokens() {
+        let preprocessed = preprocess("#define UP Ook. Ook.\nOok! Ook. UP", "test.ook").unwrap();
+        let source_map = preprocessed.instruction_map(Some(&TokenSet::ook()));
+
+        // "\n.+"
+        assert_eq!(
+            source_map.get(1).unwrap().position,
+            Position { line: 2, column: 1 }
+        );
+        assert_eq!(
+            source_map.get(2).unwrap().position,
+            Position {
+                line: 1,
+                column: 12
+            }
+        );
+    }
+
+    #[test]
+    /// Included files should be mapped back to their own positions
+    fn includes() {
+        let dir = env::temp_dir().join(format!("aneurysm-preprocessor-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("lib.bf"), "#define TWICE(x) x x\n").unwrap();
+        fs::write(dir.join("main.bf"), "#include \"lib.bf\"\nTWICE(.)").unwrap();
+        fs::write(dir.join("loop.bf"), "#include \"loop.bf\"").unwrap();
+
+        let preprocessed = preprocess_file(dir.join("main.bf")).unwrap();
+        assert_eq!(preprocessed.code, "\n\n. .");
+
+        // the dots come from the invocation, the space from the macro's body
+        let location = preprocessed.source_map.get(2).unwrap();
+        assert_eq!(location.file, dir.join("main.bf"));
+        assert_eq!(location.position, Position { line: 2, column: 7 });
+        let location = preprocessed.source_map.get(3).unwrap();
+        assert_eq!(location.file, dir.join("lib.bf"));
+        assert_eq!(
+            location.position,
+            Position {
+                line: 1,
+                column: 19
+            }
+        );
+
+        let err = preprocess_file(dir.join("loop.bf")).unwrap_err();
+        assert!(matches!(err.kind, PreprocessErrorKind::RecursiveInclude(_)));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,92 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// A position inside a source file (both the line and the column start from 1)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self { line: 1, column: 1 }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Calculate the position of each character of `code`
+pub fn positions(code: &[char]) -> Vec<Position> {
+    let mut current = Position::default();
+
+    code.iter()
+        .map(|c| {
+            let position = current;
+
+            if *c == '\n' {
+                current.line += 1;
+                current.column = 1;
+            } else {
+                current.column += 1;
+            }
+
+            position
+        })
+        .collect()
+}
+
+/// A position inside a specific source file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub file: PathBuf,
+    pub position: Position,
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file.display(), self.position)
+    }
+}
+
+/// Maps each character of some generated code back to where it was originally found
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+    pub(crate) files: Vec<PathBuf>,
+    /// The index of the file (in `files`) and the position inside it for each character
+    pub(crate) locations: Vec<(usize, Position)>,
+}
+
+impl SourceMap {
+    /// Map each character of `code` to its own position in `file`
+    pub fn from_code(code: &str, file: PathBuf) -> Self {
+        Self {
+            files: vec![file],
+            locations: positions(&code.chars().collect::<Vec<char>>())
+                .into_iter()
+                .map(|position| (0, position))
+                .collect(),
+        }
+    }
+
+    /// Same as [`get`](Self::get), but borrows the path of the file instead of cloning it
+    pub fn locate(&self, index: usize) -> Option<(&Path, Position)> {
+        self.locations
+            .get(index)
+            .map(|(file, position)| (self.files[*file].as_path(), *position))
+    }
+
+    /// Returns where the character at `index` was originally found
+    pub fn get(&self, index: usize) -> Option<SourceLocation> {
+        self.locations
+            .get(index)
+            .map(|(file, position)| SourceLocation {
+                file: self.files[*file].clone(),
+                position: *position,
+            })
+    }
+}
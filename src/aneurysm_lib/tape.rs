@@ -0,0 +1,289 @@
+use std::fmt;
+use std::str::FromStr;
+
+use displaydoc::Display;
+
+/// The kind of values a cell can hold
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum CellType {
+    /// 8-bit cells, wrapping around on overflow
+    #[default]
+    Byte,
+    /// 1-bit cells, which are flipped by `+` (used by [Boolfuck](https://esolangs.org/wiki/Boolfuck))
+    Bit,
+    /// Cells that hold a whole Unicode scalar value, which is what `.` and `,` write and read (encoded as UTF-8)
+    Wide,
+}
+
+impl CellType {
+    /// All the supported cell types
+    pub const ALL: &'static [CellType] = &[CellType::Byte, CellType::Bit, CellType::Wide];
+
+    /// The name of the cell type, as it is parsed by [`FromStr`]
+    pub fn name(&self) -> &'static str {
+        match self {
+            CellType::Byte => "byte",
+            CellType::Bit => "bit",
+            CellType::Wide => "wide",
+        }
+    }
+
+    /// The largest value a cell can hold
+    pub fn max_value(&self) -> u32 {
+        match self {
+            CellType::Byte => u8::MAX as u32,
+            CellType::Bit => 1,
+            CellType::Wide => char::MAX as u32,
+        }
+    }
+
+    /// How many hex digits it takes to print any value of a cell
+    pub fn hex_digits(&self) -> usize {
+        (u32::BITS - self.max_value().leading_zeros()).div_ceil(4) as usize
+    }
+}
+
+impl fmt::Display for CellType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for CellType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CellType::ALL
+            .iter()
+            .find(|cell_type| cell_type.name().eq_ignore_ascii_case(s))
+            .copied()
+            .ok_or_else(|| {
+                format!(
+                    "unknown cell type \"{}\" (possible values: {})",
+                    s,
+                    CellType::ALL
+                        .iter()
+                        .map(|cell_type| cell_type.name())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })
+    }
+}
+
+/// The memory of the interpreter, abstracting over the type of its cells
+#[derive(Clone, PartialEq, Eq)]
+pub enum Tape {
+    Byte(Vec<u8>),
+    Bit(Vec<bool>),
+    Wide(Vec<u32>),
+}
+
+impl Tape {
+    /// Allocate a tape of `len` cells, all set to 0
+    pub fn new(cell_type: CellType, len: usize) -> Self {
+        match cell_type {
+            CellType::Byte => Tape::Byte(vec![0; len]),
+            CellType::Bit => Tape::Bit(vec![false; len]),
+            CellType::Wide => Tape::Wide(vec![0; len]),
+        }
+    }
+
+    pub fn cell_type(&self) -> CellType {
+        match self {
+            Tape::Byte(_) => CellType::Byte,
+            Tape::Bit(_) => CellType::Bit,
+            Tape::Wide(_) => CellType::Wide,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Tape::Byte(cells) => cells.len(),
+            Tape::Bit(cells) => cells.len(),
+            Tape::Wide(cells) => cells.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The value of the cell at `index`
+    pub fn get(&self, index: usize) -> u32 {
+        match self {
+            Tape::Byte(cells) => cells[index] as u32,
+            Tape::Bit(cells) => cells[index] as u32,
+            Tape::Wide(cells) => cells[index],
+        }
+    }
+
+    /// Set the cell at `index` to `value`, wrapping it around if it doesn't fit
+    pub fn set(&mut self, index: usize, value: u32) {
+        match self {
+            Tape::Byte(cells) => cells[index] = value as u8,
+            Tape::Bit(cells) => cells[index] = value & 1 == 1,
+            Tape::Wide(cells) => cells[index] = value % (char::MAX as u32 + 1),
+        }
+    }
+
+    /// Add 1 to the cell at `index`, wrapping around on overflow
+    pub fn increment(&mut self, index: usize) {
+        let value = self.get(index);
+        let max = self.cell_type().max_value();
+        self.set(index, if value == max { 0 } else { value + 1 })
+    }
+
+    /// Subtract 1 from the cell at `index`, wrapping around on underflow
+    pub fn decrement(&mut self, index: usize) {
+        let value = self.get(index);
+        let max = self.cell_type().max_value();
+        self.set(index, if value == 0 { max } else { value - 1 })
+    }
+
+    /// Set every cell to 0
+    pub fn clear(&mut self) {
+        match self {
+            Tape::Byte(cells) => cells.iter_mut().for_each(|cell| *cell = 0),
+            Tape::Bit(cells) => cells.iter_mut().for_each(|cell| *cell = false),
+            Tape::Wide(cells) => cells.iter_mut().for_each(|cell| *cell = 0),
+        }
+    }
+
+    /// How many bytes of a tape image (see [`to_image`](Self::to_image)) hold the given number of cells
+    fn image_len(cell_type: CellType, cells: usize) -> usize {
+        match cell_type {
+            CellType::Byte => cells,
+            CellType::Bit => cells.div_ceil(8),
+            CellType::Wide => cells * 4,
+        }
+    }
+
+    /// Copy a tape image into the first cells of the tape, leaving the rest of them untouched
+    ///
+    /// Images hold one byte per byte cell, eight bit cells per byte (least significant bit first, like the I/O of Boolfuck)
+    /// and four little-endian bytes per wide cell (whose values are wrapped around if they don't fit)
+    pub fn load_image(&mut self, image: &[u8]) -> Result<(), TapeError> {
+        let cell_type = self.cell_type();
+        if cell_type == CellType::Wide && !image.len().is_multiple_of(4) {
+            return Err(TapeError::MisalignedImage(image.len()));
+        }
+        if image.len() > Self::image_len(cell_type, self.len()) {
+            return Err(TapeError::ImageTooLarge {
+                image: image.len(),
+                tape: Self::image_len(cell_type, self.len()),
+            });
+        }
+
+        match self {
+            Tape::Byte(cells) => cells[..image.len()].copy_from_slice(image),
+            Tape::Bit(cells) => {
+                for (index, cell) in cells.iter_mut().enumerate().take(image.len() * 8) {
+                    *cell = image[index / 8] >> (index % 8) & 1 == 1;
+                }
+            }
+            Tape::Wide(_) => {
+                for (index, bytes) in image.chunks_exact(4).enumerate() {
+                    self.set(index, u32::from_le_bytes(bytes.try_into().unwrap()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Encode the whole tape as an image that can be loaded with [`load_image`](Self::load_image)
+    pub fn to_image(&self) -> Vec<u8> {
+        match self {
+            Tape::Byte(cells) => cells.clone(),
+            Tape::Bit(cells) => cells
+                .chunks(8)
+                .map(|bits| {
+                    bits.iter()
+                        .enumerate()
+                        .fold(0, |byte, (index, bit)| byte | (*bit as u8) << index)
+                })
+                .collect(),
+            Tape::Wide(cells) => cells.iter().flat_map(|cell| cell.to_le_bytes()).collect(),
+        }
+    }
+}
+
+#[derive(Display, thiserror::Error, Debug, PartialEq, Eq)]
+pub enum TapeError {
+    /// The tape image is {image} bytes long, but the tape only holds {tape} bytes (try allocating more memory)
+    ImageTooLarge { image: usize, tape: usize },
+    /// The tape image is {0} bytes long, which isn't a multiple of the 4 bytes each wide cell takes
+    MisalignedImage(usize),
+}
+
+impl fmt::Debug for Tape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the whole tape would be way too long to print
+        f.debug_struct("Tape")
+            .field("cell_type", &self.cell_type())
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Values should wrap around according to the cell type
+    fn wrapping() {
+        let mut bytes = Tape::new(CellType::Byte, 2);
+        bytes.decrement(0);
+        bytes.set(1, 0x1ff);
+        assert_eq!((bytes.get(0), bytes.get(1)), (255, 255));
+
+        let mut bits = Tape::new(CellType::Bit, 2);
+        bits.increment(0);
+        bits.increment(1);
+        bits.increment(1);
+        assert_eq!((bits.get(0), bits.get(1)), (1, 0));
+
+        bits.clear();
+        assert_eq!(bits.get(0), 0);
+
+        let mut wide = Tape::new(CellType::Wide, 1);
+        wide.decrement(0);
+        assert_eq!(wide.get(0), 0x10ffff);
+        wide.increment(0);
+        assert_eq!(wide.get(0), 0);
+
+        assert_eq!(
+            CellType::ALL
+                .iter()
+                .map(|cell_type| cell_type.hex_digits())
+                .collect::<Vec<_>>(),
+            [2, 1, 6]
+        );
+    }
+
+    #[test]
+    /// Images should be loaded back as they were dumped
+    fn images() {
+        let mut bytes = Tape::new(CellType::Byte, 4);
+        bytes.load_image(&[1, 2, 3]).unwrap();
+        assert_eq!(bytes.to_image(), [1, 2, 3, 0]);
+        assert_eq!(
+            bytes.load_image(&[0; 5]),
+            Err(TapeError::ImageTooLarge { image: 5, tape: 4 })
+        );
+
+        let mut bits = Tape::new(CellType::Bit, 10);
+        bits.load_image(&[0b0000_0101, 0b10]).unwrap();
+        assert_eq!((bits.get(0), bits.get(1), bits.get(2)), (1, 0, 1));
+        assert_eq!(bits.get(9), 1);
+        assert_eq!(bits.to_image(), [0b0000_0101, 0b10]);
+
+        let mut wide = Tape::new(CellType::Wide, 2);
+        wide.load_image(&0x1f600u32.to_le_bytes()).unwrap();
+        assert_eq!(wide.get(0), 0x1f600);
+        assert_eq!(wide.to_image().len(), 8);
+        assert_eq!(wide.load_image(&[0; 3]), Err(TapeError::MisalignedImage(3)));
+    }
+}
@@ -0,0 +1,363 @@
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use displaydoc::Display;
+
+use crate::interpreter::Dialect;
+use crate::source::*;
+
+/// The eight Brainf**k instructions, which every token set must define
+pub const INSTRUCTIONS: [char; 8] = ['>', '<', '+', '-', '.', ',', '[', ']'];
+
+/// The tokens of [Ook!](https://esolangs.org/wiki/Ook!)
+const OOK: [(&str, char); 8] = [
+    ("Ook. Ook?", '>'),
+    ("Ook? Ook.", '<'),
+    ("Ook. Ook.", '+'),
+    ("Ook! Ook!", '-'),
+    ("Ook! Ook.", '.'),
+    ("Ook. Ook!", ','),
+    ("Ook! Ook?", '['),
+    ("Ook? Ook!", ']'),
+];
+
+/// A mapping between (possibly multi-word) tokens and Brainf**k instructions
+///
+/// Whitespace between the words of a token is ignored when matching, anything that doesn't match a token is treated as a comment
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TokenSet {
+    /// The tokens and the instructions they map to, longest tokens first
+    tokens: Vec<(String, char)>,
+}
+
+/// A token found in some source code
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Token {
+    /// The range of characters the token spans
+    pub range: Range<usize>,
+    pub instruction: char,
+}
+
+impl TokenSet {
+    /// Create a new token set, making sure that no token is empty or defined twice
+    pub fn new<S>(tokens: impl IntoIterator<Item = (S, char)>) -> Result<Self, TokenSetError>
+    where
+        S: ToString,
+    {
+        let mut set: Vec<(String, char)> = Vec::new();
+
+        for (token, instruction) in tokens {
+            // normalize whitespace, it doesn't matter when matching anyway
+            let token = token
+                .to_string()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            if token.is_empty() {
+                return Err(TokenSetError::EmptyToken(instruction));
+            }
+            if set.iter().any(|(other, _)| *other == token) {
+                return Err(TokenSetError::DuplicateToken(token));
+            }
+            if set.iter().any(|(_, other)| *other == instruction) {
+                return Err(TokenSetError::DuplicateInstruction(instruction));
+            }
+
+            set.push((token, instruction));
+        }
+
+        // try the longest tokens first, so that tokens can be prefixes of one another
+        set.sort_by_key(|(token, _)| std::cmp::Reverse(token.chars().count()));
+
+        Ok(Self { tokens: set })
+    }
+
+    /// The tokens of [Ook!](https://esolangs.org/wiki/Ook!)
+    pub fn ook() -> Self {
+        Self::new(OOK).unwrap()
+    }
+
+    /// The tokens of [Blub](https://esolangs.org/wiki/Blub), which is Ook! for fish
+    pub fn blub() -> Self {
+        Self::new(
+            OOK.iter()
+                .map(|(token, instruction)| (token.replace("Ook", "Blub"), *instruction)),
+        )
+        .unwrap()
+    }
+
+    /// A token set in which each instruction of `dialect` is its own token
+    pub fn native(dialect: Dialect) -> Self {
+        Self::new(
+            dialect
+                .instructions()
+                .iter()
+                .map(|instruction| (instruction, *instruction)),
+        )
+        .unwrap()
+    }
+
+    /// Parse a user-defined token set
+    ///
+    /// Each non-empty line that doesn't start with `#` must contain a Brainf**k instruction followed by the token it maps to, like `+ Ook. Ook.`.
+    /// All eight instructions must be defined exactly once
+    pub fn parse(text: &str) -> Result<Self, TokenSetError> {
+        let mut tokens = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut chars = line.chars();
+            let instruction = chars.next().unwrap();
+            if !INSTRUCTIONS.contains(&instruction) {
+                return Err(TokenSetError::InvalidLine(index + 1));
+            }
+
+            tokens.push((chars.as_str().to_string(), instruction));
+        }
+
+        let set = Self::new(tokens)?;
+
+        if let Some(missing) = INSTRUCTIONS
+            .iter()
+            .find(|instruction| set.token(**instruction).is_none())
+        {
+            return Err(TokenSetError::MissingInstruction(*missing));
+        }
+
+        Ok(set)
+    }
+
+    /// Read and parse a user-defined token set (see [`parse`](Self::parse))
+    pub fn from_path<P>(path: P) -> Result<Self, TokenSetError>
+    where
+        P: AsRef<Path>,
+    {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// The token an instruction is mapped to
+    pub fn token(&self, instruction: char) -> Option<&str> {
+        self.tokens
+            .iter()
+            .find(|(_, other)| *other == instruction)
+            .map(|(token, _)| token.as_str())
+    }
+
+    /// Find all tokens in `code`
+    pub fn tokenize(&self, code: &str) -> Vec<Token> {
+        let code = code.chars().collect::<Vec<char>>();
+        let words = self
+            .tokens
+            .iter()
+            .map(|(token, instruction)| {
+                let words = token
+                    .split(' ')
+                    .map(|word| word.chars().collect::<Vec<_>>())
+                    .collect::<Vec<_>>();
+                (words, *instruction)
+            })
+            .collect::<Vec<_>>();
+
+        let mut tokens = Vec::new();
+        let mut index = 0;
+
+        while index < code.len() {
+            match words.iter().find_map(|(words, instruction)| {
+                Self::match_words(&code, index, words).map(|end| (end, *instruction))
+            }) {
+                Some((end, instruction)) => {
+                    tokens.push(Token {
+                        range: index..end,
+                        instruction,
+                    });
+                    index = end;
+                }
+                None => index += 1,
+            }
+        }
+
+        tokens
+    }
+
+    /// If the words of a token are found at `start`, return the index right after them
+    fn match_words(code: &[char], start: usize, words: &[Vec<char>]) -> Option<usize> {
+        let mut index = start;
+
+        for (i, word) in words.iter().enumerate() {
+            if i > 0 {
+                while code.get(index).is_some_and(|c| c.is_whitespace()) {
+                    index += 1;
+                }
+            }
+
+            if !code[index..].starts_with(word) {
+                return None;
+            }
+            index += word.len();
+        }
+
+        Some(index)
+    }
+
+    /// Translate `code` into the instructions its tokens map to, along with the index in `code` of each character of the result
+    ///
+    /// Comments are dropped, but line breaks are kept
+    fn translate_with_indices(&self, code: &str) -> (String, Vec<usize>) {
+        let chars = code.chars().collect::<Vec<char>>();
+
+        let mut output = Vec::new();
+        let mut last_end = 0;
+
+        for token in self.tokenize(code) {
+            output.extend(
+                (last_end..token.range.start)
+                    .filter(|index| chars[*index] == '\n')
+                    .map(|index| ('\n', index)),
+            );
+            output.push((token.instruction, token.range.start));
+            last_end = token.range.end;
+        }
+        output.extend(
+            (last_end..chars.len())
+                .filter(|index| chars[*index] == '\n')
+                .map(|index| ('\n', index)),
+        );
+
+        output.into_iter().unzip()
+    }
+
+    /// Translate `code` into the instructions its tokens map to
+    ///
+    /// Comments are dropped, but line breaks are kept
+    pub fn to_instructions(&self, code: &str) -> String {
+        self.translate_with_indices(code).0
+    }
+
+    /// Same as [`to_instructions`](Self::to_instructions), but returns a [`SourceMap`] pointing each instruction to its token in `file`
+    pub fn source_map(&self, code: &str, file: PathBuf) -> SourceMap {
+        let (_, indices) = self.translate_with_indices(code);
+        let positions = positions(&code.chars().collect::<Vec<char>>());
+
+        SourceMap {
+            files: vec![file],
+            locations: indices
+                .into_iter()
+                .map(|index| (0, positions[index]))
+                .collect(),
+        }
+    }
+
+    /// Same as [`source_map`](Self::source_map), but for generated code (like the output of the [preprocessor](crate::preprocessor)):
+    /// each instruction points to where its token was originally found, according to `code_map`
+    pub fn map_through(&self, code: &str, code_map: &SourceMap) -> SourceMap {
+        let (_, indices) = self.translate_with_indices(code);
+
+        SourceMap {
+            files: code_map.files.clone(),
+            locations: indices
+                .into_iter()
+                .map(|index| code_map.locations[index])
+                .collect(),
+        }
+    }
+
+    /// Translate instructions (as returned by [`to_instructions`](Self::to_instructions)) into tokens of this set
+    ///
+    /// Tokens are separated by spaces, unless each token is a single character.
+    /// Returns [`TokenSetError::Untranslatable`] if an instruction doesn't have a token in this set
+    pub fn from_instructions(&self, code: &str) -> Result<String, TokenSetError> {
+        let separator = if self
+            .tokens
+            .iter()
+            .all(|(token, _)| token.chars().count() == 1)
+        {
+            ""
+        } else {
+            " "
+        };
+
+        code.split('\n')
+            .map(|line| {
+                line.chars()
+                    .filter(|c| !c.is_whitespace())
+                    .map(|c| self.token(c).ok_or(TokenSetError::Untranslatable(c)))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(|tokens| tokens.join(separator))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(|lines| lines.join("\n"))
+    }
+}
+
+#[derive(Display, thiserror::Error, Debug)]
+pub enum TokenSetError {
+    /// {0}
+    IOError(#[from] io::Error),
+    /// Line {0} doesn't start with a Brainf**k instruction
+    InvalidLine(usize),
+    /// The token of instruction '{0}' is empty
+    EmptyToken(char),
+    /// Token "{0}" is defined more than once
+    DuplicateToken(String),
+    /// Instruction '{0}' is defined more than once
+    DuplicateInstruction(char),
+    /// Instruction '{0}' isn't defined
+    MissingInstruction(char),
+    /// Instruction '{0}' can't be translated into this dialect
+    Untranslatable(char),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Ook! should translate to Brainf**k and back, no matter how the words are spaced out
+    fn ook() {
+        const PROGRAM: &str = "Ook. Ook. Ook! Ook?\nsome comment Ook!Ook!\tOok? Ook!";
+
+        let instructions = TokenSet::ook().to_instructions(PROGRAM);
+        assert_eq!(instructions, "+[\n-]");
+        assert_eq!(
+            TokenSet::ook().from_instructions(&instructions).unwrap(),
+            "Ook. Ook. Ook! Ook?\nOok! Ook! Ook? Ook!"
+        );
+        assert_eq!(
+            TokenSet::blub().from_instructions("+-").unwrap(),
+            "Blub. Blub. Blub! Blub!"
+        );
+        assert!(matches!(
+            TokenSet::ook().from_instructions(":"),
+            Err(TokenSetError::Untranslatable(':'))
+        ));
+    }
+
+    #[test]
+    fn user_defined() {
+        const TOKENS: &str =
+            "# a comment\n> right\n< left\n+ up\n- down\n. out\n, in\n[ begin\n] end\n";
+
+        let set = TokenSet::parse(TOKENS).unwrap();
+        assert_eq!(set.to_instructions("up up begin down end, out"), "++[-].");
+
+        assert!(matches!(
+            TokenSet::parse("> right\n< right"),
+            Err(TokenSetError::DuplicateToken(_))
+        ));
+        assert!(matches!(
+            TokenSet::parse("x right"),
+            Err(TokenSetError::InvalidLine(1))
+        ));
+        assert!(matches!(
+            TokenSet::parse("> right"),
+            Err(TokenSetError::MissingInstruction('<'))
+        ));
+    }
+}
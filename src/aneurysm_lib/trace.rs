@@ -0,0 +1,354 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::ops::Range;
+use std::str::FromStr;
+
+use displaydoc::Display;
+use serde_json::json;
+
+use crate::interpreter::Step;
+use crate::source::Position;
+
+/// The bytes every binary trace starts with
+pub const TRACE_MAGIC: &[u8; 4] = b"BFTR";
+/// The version of the binary format, which is bumped whenever the layout of the records changes
+pub const TRACE_VERSION: u8 = 1;
+/// The size of each record of a binary trace, in bytes
+pub const RECORD_SIZE: usize = 41;
+
+/// How traces are written
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum TraceFormat {
+    /// A header followed by fixed-size little-endian records (see [`TraceRecord::to_bytes`]), which can be read back with a [`TraceReader`]
+    #[default]
+    Binary,
+    /// One JSON object per line
+    Jsonl,
+}
+
+impl TraceFormat {
+    /// All the supported formats
+    pub const ALL: &'static [TraceFormat] = &[TraceFormat::Binary, TraceFormat::Jsonl];
+
+    /// The name of the format, as it is parsed by [`FromStr`]
+    pub fn name(&self) -> &'static str {
+        match self {
+            TraceFormat::Binary => "binary",
+            TraceFormat::Jsonl => "jsonl",
+        }
+    }
+}
+
+impl fmt::Display for TraceFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for TraceFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TraceFormat::ALL
+            .iter()
+            .find(|format| format.name().eq_ignore_ascii_case(s))
+            .copied()
+            .ok_or_else(|| {
+                format!(
+                    "unknown trace format \"{}\" (possible values: {})",
+                    s,
+                    TraceFormat::ALL
+                        .iter()
+                        .map(|format| format.name())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })
+    }
+}
+
+/// Parse a range of instruction indexes, like `10..20`, `10..` or `..20` (the end is exclusive)
+pub fn parse_range(s: &str) -> Result<Range<usize>, String> {
+    let (start, end) = s
+        .split_once("..")
+        .ok_or_else(|| format!("invalid range \"{}\" (expected something like 10..20)", s))?;
+    let parse = |bound: &str, default: usize| match bound {
+        "" => Ok(default),
+        _ => bound
+            .parse::<usize>()
+            .map_err(|err| format!("invalid range bound \"{}\": {}", bound, err)),
+    };
+
+    Ok(parse(start, 0)?..parse(end, usize::MAX)?)
+}
+
+/// A single executed instruction, as stored in a trace
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraceRecord {
+    /// The number of the step, starting from 1
+    pub step: u64,
+    pub instruction_pointer: usize,
+    /// Where the instruction is found in the source file, if known
+    pub position: Option<Position>,
+    pub instruction: char,
+    pub data_pointer: usize,
+    /// The value of the current cell before the instruction was executed
+    pub before: u32,
+    /// The value of the current cell after the instruction was executed
+    pub after: u32,
+}
+
+impl TraceRecord {
+    pub fn new(step: &Step, position: Option<Position>) -> Self {
+        Self {
+            step: step.number,
+            instruction_pointer: step.instruction_pointer,
+            position,
+            instruction: step.instruction,
+            data_pointer: step.data_pointer,
+            before: step.before,
+            after: step.after,
+        }
+    }
+
+    /// Encode the record as the step (`u64`), the instruction index (`u64`), the line and column (`u32` each, 0 if unknown),
+    /// the instruction (an ASCII byte), the data pointer (`u64`) and the cell value before and after the instruction (`u32` each)
+    pub fn to_bytes(&self) -> [u8; RECORD_SIZE] {
+        let (line, column) = self
+            .position
+            .map_or((0, 0), |position| (position.line, position.column));
+
+        let mut bytes = [0u8; RECORD_SIZE];
+        bytes[0..8].copy_from_slice(&self.step.to_le_bytes());
+        bytes[8..16].copy_from_slice(&(self.instruction_pointer as u64).to_le_bytes());
+        bytes[16..20].copy_from_slice(&(line as u32).to_le_bytes());
+        bytes[20..24].copy_from_slice(&(column as u32).to_le_bytes());
+        bytes[24] = if self.instruction.is_ascii() {
+            self.instruction as u8
+        } else {
+            b'?'
+        };
+        bytes[25..33].copy_from_slice(&(self.data_pointer as u64).to_le_bytes());
+        bytes[33..37].copy_from_slice(&self.before.to_le_bytes());
+        bytes[37..41].copy_from_slice(&self.after.to_le_bytes());
+        bytes
+    }
+
+    /// Decode a record encoded by [`to_bytes`](Self::to_bytes)
+    pub fn from_bytes(bytes: &[u8; RECORD_SIZE]) -> Self {
+        let u64_at = |start: usize| u64::from_le_bytes(bytes[start..start + 8].try_into().unwrap());
+        let u32_at = |start: usize| u32::from_le_bytes(bytes[start..start + 4].try_into().unwrap());
+
+        let (line, column) = (u32_at(16) as usize, u32_at(20) as usize);
+        Self {
+            step: u64_at(0),
+            instruction_pointer: u64_at(8) as usize,
+            position: (line > 0).then_some(Position { line, column }),
+            instruction: bytes[24] as char,
+            data_pointer: u64_at(25) as usize,
+            before: u32_at(33),
+            after: u32_at(37),
+        }
+    }
+
+    /// Encode the record as a single-line JSON object
+    pub fn to_json(&self) -> String {
+        json!({
+            "step": self.step,
+            "instruction": self.instruction_pointer,
+            "line": self.position.map(|position| position.line),
+            "column": self.position.map(|position| position.column),
+            "opcode": self.instruction.to_string(),
+            "data_pointer": self.data_pointer,
+            "before": self.before,
+            "after": self.after,
+        })
+        .to_string()
+    }
+}
+
+/// Writes the records of a trace, skipping the ones outside of the given range of instructions
+pub struct TraceWriter<W: Write> {
+    writer: W,
+    format: TraceFormat,
+    range: Option<Range<usize>>,
+}
+
+impl<W: Write> TraceWriter<W> {
+    /// Create a new writer (writing the header of the format right away)
+    pub fn new(mut writer: W, format: TraceFormat) -> io::Result<Self> {
+        if format == TraceFormat::Binary {
+            writer.write_all(TRACE_MAGIC)?;
+            writer.write_all(&[TRACE_VERSION])?;
+        }
+
+        Ok(Self {
+            writer,
+            format,
+            range: None,
+        })
+    }
+
+    /// Only write records of instructions whose index is in `range`, which keeps traces of long runs manageable
+    pub fn with_range(mut self, range: Range<usize>) -> Self {
+        self.range = Some(range);
+        self
+    }
+
+    pub fn write(&mut self, record: &TraceRecord) -> io::Result<()> {
+        if self
+            .range
+            .as_ref()
+            .is_some_and(|range| !range.contains(&record.instruction_pointer))
+        {
+            return Ok(());
+        }
+
+        match self.format {
+            TraceFormat::Binary => self.writer.write_all(&record.to_bytes()),
+            TraceFormat::Jsonl => writeln!(self.writer, "{}", record.to_json()),
+        }
+    }
+
+    /// Flush the underlying writer and return it
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Reads back the records of a binary trace, skipping the ones outside of the given range of instructions
+pub struct TraceReader<R: Read> {
+    reader: R,
+    range: Option<Range<usize>>,
+}
+
+impl<R: Read> TraceReader<R> {
+    /// Create a new reader, making sure the trace starts with a valid header
+    pub fn new(mut reader: R) -> Result<Self, TraceError> {
+        let mut header = [0u8; 5];
+        reader.read_exact(&mut header)?;
+        if header[..4] != TRACE_MAGIC[..] || header[4] != TRACE_VERSION {
+            return Err(TraceError::InvalidHeader);
+        }
+
+        Ok(Self {
+            reader,
+            range: None,
+        })
+    }
+
+    /// Only return records of instructions whose index is in `range`
+    pub fn with_range(mut self, range: Range<usize>) -> Self {
+        self.range = Some(range);
+        self
+    }
+}
+
+impl<R: Read> Iterator for TraceReader<R> {
+    type Item = Result<TraceRecord, TraceError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let mut bytes = [0u8; RECORD_SIZE];
+            match self.reader.read_exact(&mut bytes) {
+                Ok(()) => (),
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return None,
+                Err(err) => return Some(Err(err.into())),
+            }
+
+            let record = TraceRecord::from_bytes(&bytes);
+            if self
+                .range
+                .as_ref()
+                .is_none_or(|range| range.contains(&record.instruction_pointer))
+            {
+                return Some(Ok(record));
+            }
+        }
+    }
+}
+
+#[derive(Display, thiserror::Error, Debug)]
+pub enum TraceError {
+    /// {0}
+    IOError(#[from] io::Error),
+    /// The file isn't a binary trace (or it was written by an incompatible version)
+    InvalidHeader,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::*;
+
+    /// Trace a program, returning the records of its instructions
+    fn trace(code: &str) -> Vec<TraceRecord> {
+        let mut interpreter = Interpreter::new(code, InterpreterOptions::release()).unwrap();
+        let mut records = Vec::new();
+
+        while let Some(step) = interpreter.run_step_traced().unwrap() {
+            records.push(TraceRecord::new(&step, None));
+        }
+        records
+    }
+
+    #[test]
+    /// Binary traces should be read back as they were written, filtered by instruction range
+    fn binary() {
+        let records = trace("++[->+<]");
+        assert_eq!(records.len(), 13);
+        assert_eq!((records[2].instruction, records[2].before), ('[', 2));
+        assert_eq!((records[3].before, records[3].after), (2, 1));
+
+        let mut writer = TraceWriter::new(Vec::new(), TraceFormat::Binary).unwrap();
+        for record in &records {
+            writer.write(record).unwrap();
+        }
+        let bytes = writer.finish().unwrap();
+        assert_eq!(bytes.len(), 5 + records.len() * RECORD_SIZE);
+
+        let read = TraceReader::new(bytes.as_slice())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(read, records);
+
+        // only the instructions inside the loop
+        let read = TraceReader::new(bytes.as_slice())
+            .unwrap()
+            .with_range(parse_range("3..7").unwrap())
+            .count();
+        assert_eq!(read, 8);
+
+        assert!(matches!(
+            TraceReader::new(&b"nope!"[..]),
+            Err(TraceError::InvalidHeader)
+        ));
+    }
+
+    #[test]
+    fn jsonl() {
+        let mut record = trace("+")[0];
+        record.position = Some(Position { line: 1, column: 1 });
+
+        let mut writer = TraceWriter::new(Vec::new(), TraceFormat::Jsonl).unwrap();
+        writer.write(&record).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&writer.finish().unwrap()).unwrap();
+
+        assert_eq!(json["opcode"], "+");
+        assert_eq!(json["line"], 1);
+        assert_eq!(
+            (json["before"].as_u64(), json["after"].as_u64()),
+            (Some(0), Some(1))
+        );
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(parse_range("10..20"), Ok(10..20));
+        assert_eq!(parse_range("10.."), Ok(10..usize::MAX));
+        assert_eq!(parse_range("..20"), Ok(0..20));
+        assert!(parse_range("10").is_err());
+    }
+}
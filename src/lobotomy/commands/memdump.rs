@@ -8,13 +8,15 @@ use thiserror;
 use term_size::dimensions as term_dimensions;
 
 use crate::StateType;
+use aneurysm::interpreter::Dialect;
+use aneurysm::tape::Tape;
 
 const ABOUT: &str = "Dumps a portion of the programs memory";
 const LONG_ABOUT: &str = concat!(
     "Also \x1B[7mhighlights\x1B[0m at which cell the data pointer is, if that cell happens to be in range",
     "\n\n",
-    "The memory will be displayed in a table-like format, the first row showing the last two digits of each index for each column ",
-    "and the last one the corresponding cell's content in hex"
+    "The memory will be displayed in a table-like format, the first row showing the last digits of each index for each column ",
+    "and the last one the corresponding cell's content in hex (as many digits as the largest value of the cell type takes, but at least two)"
 );
 
 #[derive(Parser, Debug)]
@@ -24,66 +26,121 @@ const LONG_ABOUT: &str = concat!(
     long_about = format!("{}\n{}", ABOUT, LONG_ABOUT)
 )]
 pub struct MemdumpArgs {
-    /// The "width" of the memory dump. Must be an odd number
-    #[arg(short, long, default_value_t = 9)]
-    width: usize,
+    /// The "width" of the memory dump. Must be an odd number (defaults to 9, or to the memdump-width of the configuration file)
+    #[arg(short, long)]
+    width: Option<usize>,
 
-    /// Print the memory contents as uppercase hex
-    #[arg(short = 'H', long)]
+    /// Print the memory contents as uppercase hex (the default if memdump-uppercase is set in the configuration file)
+    #[arg(short = 'H', long, overrides_with = "lowercase_hex")]
     uppercase_hex: bool,
 
+    /// Print the memory contents as lowercase hex, even if memdump-uppercase is set in the configuration file
+    #[arg(long, overrides_with = "uppercase_hex")]
+    lowercase_hex: bool,
+
     /// The start offset of the memdump
     #[arg(default_value_t = 0)]
     offset: usize,
 }
 
-// +1 for the non-existent end seperator and /3 for the cell char size plus the seperator
-fn max_cells_visible(width: usize) -> usize {
-    (width + 1) / 3
+/// The width of the memory dump, unless it is set by the configuration file
+const DEFAULT_WIDTH: usize = 9;
+
+/// How many characters each cell takes up, not counting the separator
+fn cell_digits(data: &Tape) -> usize {
+    data.cell_type().hex_digits().max(2)
+}
+
+// +1 for the non-existent end seperator and the cell char size plus the seperator for each cell
+fn max_cells_visible(width: usize, digits: usize) -> usize {
+    (width + 1) / (digits + 1)
 }
 
 enum CellType {
     Index(usize),
-    Data { byte: u8, hex_uppercase: bool },
+    Data { byte: u32, hex_uppercase: bool },
 }
 
-fn print_cell(cell: CellType, inverse: bool, sep: bool) {
+fn format_cell(cell: CellType, digits: usize, inverse: bool, sep: bool) -> String {
+    let mut formatted = String::new();
+
     // start inverse ANSI mode
     if inverse {
-        print!("\x1B[7m")
+        formatted.push_str("\x1B[7m")
     }
 
     match cell {
         CellType::Index(i) => {
-            // show only the last 2 digits of the current offset for compatibility reasons
-            print!("{:02}", i % 100)
+            // show only the last digits of the current offset, so that the columns line up
+            formatted.push_str(&format!("{:0digits$}", i % 10usize.pow(digits as u32)))
         }
         CellType::Data {
             byte,
             hex_uppercase,
         } => {
-            // print it as a 2-digit hex
+            // print it as hex, padded to the width of the column
             if !hex_uppercase {
-                print!("{:02x}", byte)
+                formatted.push_str(&format!("{:0digits$x}", byte))
             } else {
-                print!("{:02X}", byte)
+                formatted.push_str(&format!("{:0digits$X}", byte))
             }
         }
     }
 
     // reset all enabled ANSI modes
     if inverse {
-        print!("\x1B[0m")
+        formatted.push_str("\x1B[0m")
     }
 
     // print a separator between numbers
     if sep {
-        print!("|")
+        formatted.push('|')
     }
+
+    formatted
+}
+
+/// The index row and the data row of the cells from `start` to `end` (inclusive)
+fn dump_rows(
+    data: &Tape,
+    start: usize,
+    end: usize,
+    data_pointer: usize,
+    hex_uppercase: bool,
+) -> (String, String) {
+    let digits = cell_digits(data);
+
+    // this could probably look better, but it works and is readable. if u have found a cleaner way, open a PR
+    let indices = (start..=end)
+        .map(|i| format_cell(CellType::Index(i), digits, i == data_pointer, i != end))
+        .collect();
+    let cells = (start..=end)
+        .map(|i| {
+            format_cell(
+                CellType::Data {
+                    byte: data.get(i),
+                    hex_uppercase,
+                },
+                digits,
+                i == data_pointer,
+                i != end,
+            )
+        })
+        .collect();
+
+    (indices, cells)
 }
 
 pub fn memdump(state: &mut StateType, args: MemdumpArgs) -> Result<(), Box<dyn Error>> {
-    if args.width % 2 == 0 {
+    // we won't be mutating anything, so this is a normal borrow
+    let state = state.borrow();
+
+    let config = &state.config.lobotomy;
+    let width = args.width.or(config.memdump_width).unwrap_or(DEFAULT_WIDTH);
+    let uppercase_hex =
+        !args.lowercase_hex && (args.uppercase_hex || config.memdump_uppercase.unwrap_or(false));
+
+    if width.is_multiple_of(2) {
         let mut cmd = MemdumpArgs::command();
         cmd.error(
             clap::error::ErrorKind::ValueValidation,
@@ -94,29 +151,27 @@ pub fn memdump(state: &mut StateType, args: MemdumpArgs) -> Result<(), Box<dyn E
         return Ok(());
     }
 
+    let digits = cell_digits(&state.interpreter.data);
     let dimensions = term_dimensions().unwrap();
-    if args.width > max_cells_visible(dimensions.0) {
+    if width > max_cells_visible(dimensions.0, digits) {
         eprintln!(
             "{}",
             MemdumpError::TerminalTooSmall {
                 width: dimensions.0,
-                cells: max_cells_visible(dimensions.0),
-                provided: args.width
+                cells: max_cells_visible(dimensions.0, digits),
+                provided: width
             }
         );
         return Ok(());
     }
 
-    // we won't be mutating anything, so this is a normal borrow
-    let state = state.borrow();
-
     // Let's now check if the offset parameter, combined with width, is in bounds of the cell array
-    if args.offset + args.width > state.interpreter.data.len() {
+    if args.offset + width > state.interpreter.data.len() {
         eprintln!(
             "{}",
             MemdumpError::OutOfBounds {
-                limit: args.offset + args.width,
-                overflown: args.offset + args.width - state.interpreter.data.len(),
+                limit: args.offset + width,
+                overflown: args.offset + width - state.interpreter.data.len(),
                 end: state.interpreter.data.len(),
             }
         );
@@ -125,29 +180,32 @@ pub fn memdump(state: &mut StateType, args: MemdumpArgs) -> Result<(), Box<dyn E
 
     // we can now start dumping the memory
     let start: usize = args.offset;
-    let end: usize = args.offset + args.width;
-
-    // this could probably look better, but it works and is readable. if u have found a cleaner way, open a PR
-    for i in start..=end {
-        print_cell(
-            CellType::Index(i),
-            i == state.interpreter.data_pointer,
-            i != end,
-        )
-    }
-    println!();
-
-    for i in start..=end {
-        print_cell(
-            CellType::Data {
-                byte: state.interpreter.data[i],
-                hex_uppercase: args.uppercase_hex,
-            },
-            i == state.interpreter.data_pointer,
-            i != end,
-        )
+    let end: usize = args.offset + width;
+
+    let (indices, cells) = dump_rows(
+        &state.interpreter.data,
+        start,
+        end,
+        state.interpreter.data_pointer,
+        uppercase_hex,
+    );
+    println!("{}\n{}", indices, cells);
+
+    // Extended Type I programs also have a register
+    if state.interpreter.dialect() == Dialect::Extended {
+        println!(
+            "storage: {}",
+            format_cell(
+                CellType::Data {
+                    byte: state.interpreter.storage,
+                    hex_uppercase: uppercase_hex,
+                },
+                digits,
+                false,
+                false,
+            )
+        );
     }
-    println!();
 
     Ok(())
 }
@@ -171,3 +229,25 @@ pub enum MemdumpError {
         end: usize,
     },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aneurysm::tape::CellType;
+
+    #[test]
+    /// The index and data rows should line up, no matter how many digits the cells take
+    fn wide_cells() {
+        let mut data = Tape::new(CellType::Wide, 4);
+        data.set(1, 0x10ffff);
+        data.set(2, 0x41);
+
+        let (indices, cells) = dump_rows(&data, 0, 2, 3, true);
+        assert_eq!(indices, "000000|000001|000002");
+        assert_eq!(cells, "000000|10FFFF|000041");
+
+        let data = Tape::new(CellType::Bit, 2);
+        let (indices, cells) = dump_rows(&data, 0, 1, 3, false);
+        assert_eq!((indices.as_str(), cells.as_str()), ("00|01", "00|00"));
+    }
+}
@@ -1,8 +1,16 @@
 mod breakpoint;
 mod memdump;
 mod reload;
+mod reverse_continue;
+mod reverse_step;
 mod run;
+mod thread;
+mod where_;
 pub use breakpoint::*;
 pub use memdump::*;
 pub use reload::*;
+pub use reverse_continue::*;
+pub use reverse_step::*;
 pub use run::*;
+pub use thread::*;
+pub use where_::*;
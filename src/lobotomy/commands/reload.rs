@@ -6,8 +6,7 @@ use clap::{Parser, ValueEnum};
 use displaydoc::Display;
 use thiserror;
 
-use crate::StateType;
-use aneurysm::interpreter::*;
+use crate::{load_interpreter, LoadError, StateType};
 
 #[derive(ValueEnum, Debug, Clone)]
 pub enum ReloadMode {
@@ -37,21 +36,23 @@ pub fn reload(state: &mut StateType, args: ReloadArgs) -> Result<(), Box<dyn Err
             println!("Successfully reset interpreter")
         }
         ReloadMode::File => {
-            state.interpreter = match Interpreter::new_from_path(
-                state.filepath.clone(),
+            (state.interpreter, state.source_map) = match load_interpreter(
+                &state.filepath,
+                state.preprocess,
                 state.interpreter.get_options(),
             ) {
-                Ok(new_interpreter) => {
+                Ok(mut loaded) => {
                     println!("File \"{}\" reloaded", state.filename());
+                    loaded.0.set_stdout_echo(state.config.echo.unwrap_or(false));
                     // also don't forget to clean our breakpoints
                     state.breakpoints.clear();
-                    new_interpreter
+                    loaded
                 }
-                Err(interpreter_err) => {
+                Err(load_err) => {
                     eprintln!(
                         "An error occured while trying to reload the interpreter object for file {}. Keeping program state as-is.\nError message:\n{}",
                         state.filename(),
-                        ReloadErr::InterpreterError(interpreter_err)
+                        ReloadErr::LoadError(load_err)
                     );
                     return Ok(());
                 }
@@ -65,5 +66,5 @@ pub fn reload(state: &mut StateType, args: ReloadArgs) -> Result<(), Box<dyn Err
 #[derive(Display, thiserror::Error, Debug)]
 pub enum ReloadErr {
     /// {0}
-    InterpreterError(InterpreterError),
+    LoadError(LoadError),
 }
@@ -0,0 +1,73 @@
+// Undo instructions until a breakpoint is reached (as long as they were recorded, see the --history flag)
+
+use std::error::Error;
+
+use clap::Parser;
+use displaydoc::Display;
+
+use crate::StateType;
+
+#[derive(Parser, Debug)]
+#[command(
+    bin_name = "reverse-continue",
+    about = "Execute the program backwards until the previous breakpoint"
+)]
+pub struct ReverseContinueArgs {}
+
+pub fn reverse_continue(
+    state: &mut StateType,
+    _args: ReverseContinueArgs,
+) -> Result<(), Box<dyn Error>> {
+    let mut state = state.borrow_mut();
+
+    if !state.interpreter.is_recording() {
+        eprintln!("{}", ReverseContinueError::NotRecording);
+        return Ok(());
+    }
+    if state.interpreter.recorded_steps() == 0 {
+        eprintln!("{}", ReverseContinueError::NothingRecorded);
+        return Ok(());
+    }
+
+    while state.interpreter.step_back() {
+        let instruction_pointer = state.interpreter.instruction_pointer;
+        if state
+            .breakpoints
+            .binary_search(&instruction_pointer)
+            .is_ok()
+        {
+            eprintln!(
+                "{}",
+                ReverseContinueError::BreakpointFound(instruction_pointer)
+            );
+            if let Some(location) = state.location(instruction_pointer) {
+                eprintln!("Located at {}", location);
+            }
+
+            return Ok(());
+        }
+    }
+
+    eprintln!(
+        "{}",
+        ReverseContinueError::ReachedStart(state.interpreter.instruction_pointer)
+    );
+    Ok(())
+}
+
+#[derive(Display, thiserror::Error, Debug)]
+pub enum ReverseContinueError {
+    // Not actually an error
+    /// Found a breakpoint at index {0}
+    BreakpointFound(usize),
+
+    // Not actually an error
+    /// Reached the earliest recorded instruction (at index {0}) without finding any breakpoints
+    ReachedStart(usize),
+
+    /// There are no recorded instructions to undo
+    NothingRecorded,
+
+    /// Instructions aren't being recorded, restart lobotomy with --history <STEPS> to undo them
+    NotRecording,
+}
@@ -0,0 +1,55 @@
+// Undo the last executed instructions (as long as they were recorded, see the --history flag)
+
+use std::error::Error;
+
+use clap::Parser;
+use displaydoc::Display;
+
+use crate::StateType;
+
+#[derive(Parser, Debug)]
+#[command(
+    bin_name = "reverse-step",
+    about = "Undo the last executed instruction(s)"
+)]
+pub struct ReverseStepArgs {
+    /// How many instructions to undo
+    #[arg(default_value_t = 1)]
+    count: usize,
+}
+
+pub fn reverse_step(state: &mut StateType, args: ReverseStepArgs) -> Result<(), Box<dyn Error>> {
+    let mut state = state.borrow_mut();
+
+    if !state.interpreter.is_recording() {
+        eprintln!("{}", ReverseStepError::NotRecording);
+        return Ok(());
+    }
+
+    let mut undone = 0;
+    while undone < args.count && state.interpreter.step_back() {
+        undone += 1;
+    }
+    if undone < args.count {
+        eprintln!("{}", ReverseStepError::HistoryExhausted(undone));
+    }
+
+    let instruction_pointer = state.interpreter.instruction_pointer;
+    match state.location(instruction_pointer) {
+        Some(location) => println!(
+            "Instruction pointer at index {} (`{}` at {})",
+            instruction_pointer, state.interpreter.code[instruction_pointer], location
+        ),
+        None => println!("Instruction pointer at index {}", instruction_pointer),
+    }
+
+    Ok(())
+}
+
+#[derive(Display, thiserror::Error, Debug)]
+pub enum ReverseStepError {
+    /// Only {0} instruction(s) could be undone, no earlier ones were recorded
+    HistoryExhausted(usize),
+    /// Instructions aren't being recorded, restart lobotomy with --history <STEPS> to undo them
+    NotRecording,
+}
@@ -5,6 +5,7 @@ use displaydoc::Display;
 use thiserror;
 
 use crate::StateType;
+use aneurysm::interpreter::*;
 
 #[derive(Parser, Debug)]
 #[command(bin_name = "run", about = "Start executing the program")]
@@ -23,7 +24,10 @@ pub fn run(state: &mut StateType, args: RunArgs) -> Result<(), Box<dyn Error>> {
     }
 
     if args.ignore_breakpoints || state.breakpoints.is_empty() {
-        state.interpreter.run_to_end();
+        if let Err(err) = state.interpreter.run_to_end() {
+            eprintln!("\n{}", RunError::InterpreterError(err));
+            return Ok(());
+        }
         eprintln!("\n{}", RunError::ReachedEOF);
         return Ok(());
     } else {
@@ -44,13 +48,24 @@ pub fn run(state: &mut StateType, args: RunArgs) -> Result<(), Box<dyn Error>> {
             }); // in this case, this is a "virtual" breakpoint that will never be reached, since it is past the program's EOF
 
         loop {
-            if state.interpreter.run_step().is_none() {
-                eprintln!("\n{}", RunError::ReachedEOF);
+            match state.interpreter.run_step() {
+                Ok(Some(())) => (),
+                Ok(None) => {
+                    eprintln!("\n{}", RunError::ReachedEOF);
 
-                return Ok(());
+                    return Ok(());
+                }
+                Err(err) => {
+                    eprintln!("\n{}", RunError::InterpreterError(err));
+
+                    return Ok(());
+                }
             }
             if state.interpreter.instruction_pointer >= next_breakpoint_index {
                 eprintln!("\n{}", RunError::BreakpointFound(next_breakpoint_index),);
+                if let Some(location) = state.location(next_breakpoint_index) {
+                    eprintln!("Located at {}", location);
+                }
 
                 return Ok(());
             }
@@ -70,4 +85,7 @@ pub enum RunError {
 
     /// The instruction pointer is past the program's EOF. Use the -r flag to reset it
     PastEOF,
+
+    /// The program stopped due to an error: {0}
+    InterpreterError(InterpreterError),
 }
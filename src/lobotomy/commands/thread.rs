@@ -0,0 +1,78 @@
+// List and switch between the threads of Brainfork programs
+
+use std::error::Error;
+
+use clap::{Parser, Subcommand};
+use displaydoc::Display;
+
+use crate::StateType;
+
+#[derive(Subcommand, Debug)]
+pub enum ThreadAction {
+    /// List all threads, marking the current one with a `*`
+    List,
+    /// Make another thread the current one, so that it's executed next
+    Switch {
+        /// The identifier of the thread
+        id: usize,
+    },
+}
+
+#[derive(Parser, Debug)]
+#[command(
+    bin_name = "thread",
+    about = "List and switch between threads (Brainfork only)"
+)]
+pub struct ThreadArgs {
+    #[command(subcommand)]
+    action: ThreadAction,
+}
+
+pub fn thread(state: &mut StateType, args: ThreadArgs) -> Result<(), Box<dyn Error>> {
+    let mut state = state.borrow_mut();
+
+    match args.action {
+        ThreadAction::List => {
+            let interpreter = &state.interpreter;
+            let current = (
+                interpreter.thread_id,
+                interpreter.instruction_pointer,
+                interpreter.data_pointer,
+            );
+            let others = interpreter
+                .threads
+                .iter()
+                .map(|thread| (thread.id, thread.instruction_pointer, thread.data_pointer));
+
+            for (index, (id, instruction_pointer, data_pointer)) in
+                std::iter::once(current).chain(others).enumerate()
+            {
+                print!(
+                    "{} thread {}: instruction pointer at index {}",
+                    if index == 0 { '*' } else { ' ' },
+                    id,
+                    instruction_pointer
+                );
+                if let Some(location) = state.location(instruction_pointer) {
+                    print!(" (at {})", location)
+                }
+                println!(", data pointer at cell {}", data_pointer);
+            }
+        }
+        ThreadAction::Switch { id } => {
+            if !state.interpreter.switch_thread(id) {
+                eprintln!("{}", ThreadError::NotFound(id));
+                return Ok(());
+            }
+            println!("Switched to thread {}", id);
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Display, thiserror::Error, Debug)]
+pub enum ThreadError {
+    /// There is no thread with identifier {0}
+    NotFound(usize),
+}
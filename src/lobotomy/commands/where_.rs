@@ -0,0 +1,43 @@
+use std::error::Error;
+
+use clap::Parser;
+
+use crate::StateType;
+
+#[derive(Parser, Debug)]
+#[command(
+    bin_name = "where",
+    about = "Show where the instruction pointer is in the source file(s)"
+)]
+pub struct WhereArgs {}
+
+pub fn where_(state: &mut StateType, _args: WhereArgs) -> Result<(), Box<dyn Error>> {
+    let state = state.borrow();
+    let instruction_pointer = state.interpreter.instruction_pointer;
+
+    // only worth mentioning if the program has forked (Brainfork only)
+    if !state.interpreter.threads.is_empty() {
+        println!("In thread {}", state.interpreter.thread_id);
+    }
+
+    match state.location(instruction_pointer) {
+        Some(location) => println!(
+            "Instruction pointer at index {} (`{}` at {})",
+            instruction_pointer, state.interpreter.code[instruction_pointer], location
+        ),
+        None => println!(
+            "Instruction pointer at index {}, past the program's EOF",
+            instruction_pointer
+        ),
+    }
+
+    // show which procedures we are in (pbrain only)
+    for caller in state.interpreter.call_stack.iter().rev() {
+        match state.location(*caller) {
+            Some(location) => println!("  called from index {} (at {})", caller, location),
+            None => println!("  called from index {}", caller),
+        }
+    }
+
+    Ok(())
+}
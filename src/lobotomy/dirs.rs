@@ -4,7 +4,8 @@ use directories::ProjectDirs;
 use flexi_logger::FileSpec;
 
 fn default_project_dirs() -> ProjectDirs {
-    ProjectDirs::from("dev", "oakchris1955", "lobotomy").unwrap()
+    // shared with aneurysm, which reads its configuration file from there too
+    aneurysm::config::project_dirs().unwrap()
 }
 
 fn logs_dir() -> PathBuf {
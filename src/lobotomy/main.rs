@@ -1,13 +1,16 @@
 use std::rc::Rc;
 use std::{cell::RefCell, process::exit};
 
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches, Parser};
 use flexi_logger::{LogSpecification, Logger};
 use log::LevelFilter;
 use rustyline::DefaultEditor;
 use shellfish::{handler::DefaultHandler, *};
 
+use aneurysm::config::Config;
 use aneurysm::interpreter::*;
+use aneurysm::tape::CellType;
+use aneurysm::tokens::TokenSet;
 
 mod clap_parser;
 mod commands;
@@ -28,15 +31,61 @@ pub use state::*;
 struct Args {
     /// Path to the file to debug
     filename: std::path::PathBuf,
+
+    /// The dialect the program is written in (brainfuck, pbrain, brainfork, extended, boolfuck, ook, blub)
+    #[arg(short, long, default_value_t = Dialect::default())]
+    dialect: Dialect,
+
+    /// A file defining the tokens the program is written with (one instruction and its token per line, like "+ Ook. Ook.")
+    #[arg(short, long, value_name = "FILE")]
+    tokens: Option<std::path::PathBuf>,
+
+    /// Treat `#` as an instruction that prints the cells around the data pointer to stderr
+    #[arg(long)]
+    debug_dumps: bool,
+
+    /// Treat everything after the first `!` as the program's input
+    #[arg(long)]
+    input_separator: bool,
+
+    /// The type of the cells (byte, bit, wide). Defaults to the one of the dialect (bit for boolfuck, byte for everything else)
+    #[arg(long, value_name = "TYPE")]
+    cell_type: Option<CellType>,
+
+    /// Read non-ASCII characters from the console as UTF-8 and write output bytes as-is, so that UTF-8 output renders correctly
+    #[arg(long)]
+    utf8: bool,
+
+    /// What reading past the end of the input does to the current cell (unchanged, zero, max, error)
+    #[arg(long, value_name = "POLICY", default_value_t = EofPolicy::default())]
+    eof: EofPolicy,
+
+    /// Run the file through the preprocessor (includes, macros, repeat counts and cell aliases) before debugging it
+    #[arg(short, long)]
+    preprocess: bool,
+
+    /// Record the last executed instructions (at most this many), so that they can be undone with reverse-step and reverse-continue
+    #[arg(long, value_name = "STEPS")]
+    history: Option<usize>,
 }
 
 pub type StateType<'a, 'b> = Rc<RefCell<State<'a, 'b>>>;
 
 fn main() {
-    let args = Args::parse();
+    // the configuration file provides the defaults of the command line parameters, so it has to be loaded first
+    let (config, config_error) = match Config::load_default() {
+        Ok(config) => (config, None),
+        Err(err) => (Config::default(), Some(err)),
+    };
+    let args = Args::from_arg_matches(&apply_config(Args::command(), &config).get_matches())
+        .unwrap_or_else(|err| err.exit());
 
     // before doing ANYTHING, configure the logger
-    Logger::try_with_env_or_str(LogSpecification::info().to_string())
+    let log_spec = match config.log_level {
+        Some(level) => LogSpecification::builder().default(level).build(),
+        None => LogSpecification::info(),
+    };
+    Logger::try_with_env_or_str(log_spec.to_string())
         .unwrap()
         .log_to_file(log_filespec())
         .duplicate_to_stderr(LevelFilter::Warn.into())
@@ -44,28 +93,63 @@ fn main() {
         .start()
         .unwrap();
 
-    let interpreter = match Interpreter::new_from_path(
-        &args.filename,
-        InterpreterOptions::debug().with_cell_size(DEFAULT_CELL_SIZE),
-    ) {
-        Ok(interpreter) => interpreter,
-        Err(err) => {
-            log::error!(
-                "An error occured while opening file \"{}\":\n{}",
-                args.filename.display(),
-                err
-            );
-            exit(1)
+    if let Some(err) = config_error {
+        log::error!("{}", err);
+        exit(1)
+    }
+
+    let mut options = InterpreterOptions::debug()
+        .with_cell_size(config.memory.unwrap_or(DEFAULT_CELL_SIZE))
+        .with_dialect(args.dialect)
+        .with_debug_dumps(args.debug_dumps)
+        .with_input_separator(args.input_separator)
+        .with_utf8(args.utf8)
+        .with_eof(args.eof);
+    if let Some(steps) = args.history {
+        options = options.with_recording(steps);
+    }
+    if let Some(cell_type) = args.cell_type {
+        options = options.with_cell_type(cell_type);
+    }
+    if let Some(path) = &args.tokens {
+        match TokenSet::from_path(path) {
+            Ok(tokens) => options = options.with_tokens(tokens),
+            Err(err) => {
+                log::error!(
+                    "An error occured while loading tokens from file \"{}\":\n{}",
+                    path.display(),
+                    err
+                );
+                exit(1)
+            }
         }
-    };
+    }
+
+    let (mut interpreter, source_map) =
+        match load_interpreter(&args.filename, args.preprocess, options) {
+            Ok(loaded) => loaded,
+            Err(err) => {
+                log::error!(
+                    "An error occured while opening file \"{}\":\n{}",
+                    args.filename.display(),
+                    err
+                );
+                exit(1)
+            }
+        };
     log::debug!(
         "Successfully created interpreter from file \"{}\"",
         args.filename.display()
     );
 
+    interpreter.set_stdout_echo(config.echo.unwrap_or(false));
+
     let state: StateType = Rc::new(RefCell::new(State::new(
         interpreter,
         args.filename.canonicalize().unwrap(),
+        args.preprocess,
+        source_map,
+        config,
     )));
     let prompt = Prompt::new(state.clone());
 
@@ -94,9 +178,39 @@ fn main() {
         .commands
         .insert("reload", clap_command!(StateType, ReloadArgs, reload));
 
+    shell.commands.insert(
+        "reverse-step",
+        clap_command!(StateType, ReverseStepArgs, reverse_step),
+    );
+
+    shell.commands.insert(
+        "reverse-continue",
+        clap_command!(StateType, ReverseContinueArgs, reverse_continue),
+    );
+
+    shell
+        .commands
+        .insert("where", clap_command!(StateType, WhereArgs, where_));
+
+    shell
+        .commands
+        .insert("thread", clap_command!(StateType, ThreadArgs, thread));
+
     log::debug!("Commands injected, starting main loop...");
 
     shell.run().unwrap();
 
     log::debug!("Main loop interrupted by user. Terminating...");
 }
+
+/// Use the values of the configuration file as the defaults of the matching arguments, so that they can still be overridden
+fn apply_config(mut command: clap::Command, config: &Config) -> clap::Command {
+    if let Some(cell_type) = config.cell_type {
+        command = command.mut_arg("cell_type", |arg| arg.default_value(cell_type.to_string()));
+    }
+    if let Some(eof) = config.eof {
+        command = command.mut_arg("eof", |arg| arg.default_value(eof.to_string()));
+    }
+
+    command
+}
@@ -1,21 +1,41 @@
 use std::cell::RefCell;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
+use displaydoc::Display;
+
+use aneurysm::config::Config;
 use aneurysm::interpreter::*;
+use aneurysm::preprocessor::*;
+use aneurysm::source::*;
 
 pub struct State<'a, 'b> {
     pub interpreter: Interpreter<'a, 'b>,
     pub breakpoints: Vec<usize>,
     pub filepath: PathBuf,
+    /// Whether the file is run through the preprocessor before being loaded
+    pub preprocess: bool,
+    /// Maps the loaded code back to the original files (only if `preprocess` is set or the dialect uses tokens)
+    pub source_map: Option<SourceMap>,
+    pub config: Config,
 }
 
 impl<'a, 'b> State<'a, 'b> {
-    pub fn new(interpreter: Interpreter<'a, 'b>, filepath: PathBuf) -> Self {
+    pub fn new(
+        interpreter: Interpreter<'a, 'b>,
+        filepath: PathBuf,
+        preprocess: bool,
+        source_map: Option<SourceMap>,
+        config: Config,
+    ) -> Self {
         State {
             interpreter,
             breakpoints: Vec::new(),
             filepath,
+            preprocess,
+            source_map,
+            config,
         }
     }
 
@@ -26,6 +46,58 @@ impl<'a, 'b> State<'a, 'b> {
             .to_string_lossy()
             .to_string()
     }
+
+    /// Where the instruction at `index` was found in the original source files
+    pub fn location(&self, index: usize) -> Option<SourceLocation> {
+        match &self.source_map {
+            Some(source_map) => source_map.get(index),
+            // comments aren't removed in debug mode, so each index corresponds to a character of the file
+            None => positions(&self.interpreter.code)
+                .get(index)
+                .map(|position| SourceLocation {
+                    file: self.filepath.clone(),
+                    position: *position,
+                }),
+        }
+    }
+}
+
+/// Create an interpreter for the file at `filepath`, running it through the preprocessor first if `preprocess` is set
+///
+/// A [`SourceMap`] is also returned if the code had to be transformed before being loaded
+pub fn load_interpreter<'a, 'b>(
+    filepath: &Path,
+    preprocess: bool,
+    options: InterpreterOptions,
+) -> Result<(Interpreter<'a, 'b>, Option<SourceMap>), LoadError> {
+    if preprocess {
+        let preprocessed = preprocess_file(filepath).map_err(LoadError::PreprocessError)?;
+        // tokens are translated into single instructions, so they have to be mapped through the preprocessed code
+        let source_map = preprocessed.instruction_map(options.token_set().as_ref());
+        let interpreter =
+            Interpreter::new(preprocessed.code, options).map_err(LoadError::InterpreterError)?;
+
+        Ok((interpreter, Some(source_map)))
+    } else {
+        // tokens are translated into single instructions, so their positions must be remembered
+        let source_map = options.token_set().and_then(|tokens| {
+            fs::read_to_string(filepath)
+                .ok()
+                .map(|code| tokens.source_map(&code, filepath.to_path_buf()))
+        });
+        let interpreter =
+            Interpreter::new_from_path(filepath, options).map_err(LoadError::InterpreterError)?;
+
+        Ok((interpreter, source_map))
+    }
+}
+
+#[derive(Display, Debug)]
+pub enum LoadError {
+    /// {0}
+    InterpreterError(InterpreterError),
+    /// {0}
+    PreprocessError(PreprocessError),
 }
 
 pub struct Prompt<'a, 'b> {
@@ -38,8 +110,29 @@ impl<'a, 'b> Prompt<'a, 'b> {
     }
 }
 
+/// The prompt used unless the configuration file sets another one
+pub const DEFAULT_PROMPT: &str = "&\x1B[1m{file}\x1B[0m> ";
+
 impl<'a, 'b> std::fmt::Display for Prompt<'a, 'b> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "&\x1B[1m{}\x1B[0m> ", self.state.borrow().filename())
+        let state = self.state.borrow();
+        let format = state
+            .config
+            .lobotomy
+            .prompt
+            .as_deref()
+            .unwrap_or(DEFAULT_PROMPT);
+
+        write!(
+            f,
+            "{}",
+            format
+                .replace("{file}", &state.filename())
+                .replace(
+                    "{instruction}",
+                    &state.interpreter.instruction_pointer.to_string()
+                )
+                .replace("{pointer}", &state.interpreter.data_pointer.to_string())
+        )
     }
 }